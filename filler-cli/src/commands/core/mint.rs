@@ -25,6 +25,11 @@ pub(crate) struct MintCommand {
     #[clap(long)]
     pub(crate) amount: u64,
 
+    /// The filler config with the wallet source
+    /// Ex. config.testnet.json
+    #[clap(long, default_value = "config.testnet.json")]
+    pub(crate) config: String,
+
     /// The URL to query
     /// Ex. testnet.fuel.network
     #[clap(long)]
@@ -33,7 +38,7 @@ pub(crate) struct MintCommand {
 
 impl MintCommand {
     pub(crate) async fn run(&self) -> anyhow::Result<()> {
-        let (wallet, traders) = setup(&self.rpc, 5).await?;
+        let (wallet, traders) = setup(&self.rpc, &self.config, 5).await?;
         let contract_id = validate_contract_id(&self.contract_id)?;
 
         let base = AssetId::from_str(&self.base).expect("Invalid asset");
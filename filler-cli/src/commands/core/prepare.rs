@@ -39,6 +39,11 @@ pub(crate) struct PrepareCommand {
     #[clap(long)]
     pub(crate) gas_amount: Option<u64>,
 
+    /// The filler config with the wallet source
+    /// Ex. config.testnet.json
+    #[clap(long, default_value = "config.testnet.json")]
+    pub(crate) config: String,
+
     /// The URL to query
     /// Ex. testnet.fuel.network
     #[clap(long)]
@@ -47,7 +52,7 @@ pub(crate) struct PrepareCommand {
 
 impl PrepareCommand {
     pub(crate) async fn run(&self) -> anyhow::Result<()> {
-        let (wallet, traders) = setup(&self.rpc, self.traders_num).await?;
+        let (wallet, traders) = setup(&self.rpc, &self.config, self.traders_num).await?;
         let market_id = validate_contract_id(&self.market_id)?;
         let multiasset_id = validate_contract_id(&self.multiasset_id)?;
 
@@ -16,6 +16,11 @@ pub(crate) struct BalancesCommand {
     #[clap(long)]
     pub(crate) traders_num: usize,
 
+    /// The filler config with the wallet source
+    /// Ex. config.testnet.json
+    #[clap(long, default_value = "config.testnet.json")]
+    pub(crate) config: String,
+
     /// The URL to query
     /// Ex. testnet.fuel.network
     #[clap(long)]
@@ -24,7 +29,7 @@ pub(crate) struct BalancesCommand {
 
 impl BalancesCommand {
     pub(crate) async fn run(&self) -> anyhow::Result<()> {
-        let (wallet, traders) = setup(&self.rpc, self.traders_num).await?;
+        let (wallet, traders) = setup(&self.rpc, &self.config, self.traders_num).await?;
         let contract_id = validate_contract_id(&self.contract_id)?;
         let market_contract = SparkMarketContract::new(contract_id, wallet.clone()).await;
 
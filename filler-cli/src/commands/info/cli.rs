@@ -1,4 +1,4 @@
-use crate::commands::info::balances::BalancesCommand;
+use crate::commands::info::{balances::BalancesCommand, dead_letters::DeadLettersCommand};
 use clap::Subcommand;
 
 #[derive(Clone, Subcommand)]
@@ -6,4 +6,8 @@ pub(crate) enum InfoCommands {
     /// Query asset balances information
     #[clap(short_flag = 'B')]
     Balances(BalancesCommand),
+
+    /// Query operations that failed permanently
+    #[clap(short_flag = 'D')]
+    DeadLetters(DeadLettersCommand),
 }
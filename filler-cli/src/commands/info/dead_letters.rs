@@ -0,0 +1,29 @@
+use clap::Args;
+use std::{fs::File, io::BufReader};
+
+#[derive(Args, Clone)]
+#[command(about = "Query operations that failed permanently")]
+pub(crate) struct DeadLettersCommand {
+    /// The dead-letter file written by the filler
+    /// Ex. dead_letters.json
+    #[clap(long, default_value = "dead_letters.json")]
+    pub(crate) path: String,
+}
+
+impl DeadLettersCommand {
+    pub(crate) async fn run(&self) -> anyhow::Result<()> {
+        let file = File::open(&self.path)?;
+        let letters: Vec<serde_json::Value> = serde_json::from_reader(BufReader::new(file))?;
+
+        for (i, letter) in letters.iter().enumerate() {
+            println!(
+                "{} / {} attempts / {}",
+                i, letter["attempts"], letter["error"]
+            );
+            println!("{}", serde_json::to_string_pretty(&letter["operation"])?);
+        }
+        println!("Total: {}", letters.len());
+
+        Ok(())
+    }
+}
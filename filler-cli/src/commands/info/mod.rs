@@ -1,2 +1,3 @@
 pub(crate) mod balances;
 pub(crate) mod cli;
+pub(crate) mod dead_letters;
@@ -1,32 +1,23 @@
 use clap::ValueEnum;
-use fuels::{
-    crypto::SecretKey,
-    prelude::{ContractId, Provider, WalletUnlocked},
-};
-use std::str::FromStr;
+use filler_wallet::WalletSource;
+use fuels::prelude::{ContractId, Provider, WalletUnlocked};
+use std::{fs::File, io::BufReader, str::FromStr};
 
 pub const ETH: &str = "0xf8f8b6283d7fa5b672b530cbb84fcccb4ff8dc40f8176ef4544ddb1f1952ad07";
 
+/// Load the owner and trader wallets from the wallet source of the filler config
 pub(crate) async fn setup(
     rpc: &str,
+    config_path: &str,
     traders_num: usize,
 ) -> anyhow::Result<(WalletUnlocked, Vec<WalletUnlocked>)> {
     let provider = Provider::connect(rpc).await?;
-    let mnemonic = std::env::var("WALLET_MNEMONIC")?;
-    let wallet = WalletUnlocked::new_from_mnemonic_phrase(&mnemonic, Some(provider.clone()))?;
 
-    let traders = (0..traders_num)
-        .map(|i| {
-            let secret_key = SecretKey::new_from_mnemonic_phrase_with_path(
-                &mnemonic,
-                &format!("m/44'/60'/0'/{}", i),
-            )
-            .unwrap();
-            WalletUnlocked::new_from_private_key(secret_key, Some(provider.clone()))
-        })
-        .collect::<Vec<_>>();
+    let file = File::open(config_path)?;
+    let config: serde_json::Value = serde_json::from_reader(BufReader::new(file))?;
+    let source: WalletSource = serde_json::from_value(config["wallet"].clone())?;
 
-    Ok((wallet, traders))
+    Ok(source.load(&provider, 0, traders_num)?)
 }
 
 pub(crate) fn validate_contract_id(contract_id: &str) -> anyhow::Result<ContractId> {
@@ -22,6 +22,7 @@ async fn main() -> anyhow::Result<()> {
         },
         Command::Info(args) => match args.commands {
             InfoCommands::Balances(args) => args.run().await,
+            InfoCommands::DeadLetters(args) => args.run().await,
         },
     }
 }
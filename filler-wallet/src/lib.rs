@@ -0,0 +1,132 @@
+//! Owner and trader wallets shared by the filler and its CLI
+
+use fuels::{
+    accounts::{provider::Provider, wallet::WalletUnlocked},
+    crypto::SecretKey,
+    types::errors::{Error, Result},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Where the owner and trader keys are read from, secrets are kept in files
+/// which can be mounted as docker secrets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WalletSource {
+    /// Mnemonic phrase file, traders are derived at `m/44'/60'/0'/{i}`
+    Mnemonic { path: String },
+    /// Encrypted JSON keystores sharing the password from the file,
+    /// traders are the keystores of the directory sorted by file name
+    Keystore {
+        owner: String,
+        traders: String,
+        password_path: String,
+    },
+    /// Files with hex private keys, traders are the files of the directory sorted by name
+    PrivateKeys { owner: String, traders: String },
+}
+
+impl WalletSource {
+    /// Load the owner wallet and `num` trader wallets starting from `offset`
+    pub fn load(
+        &self,
+        provider: &Provider,
+        offset: usize,
+        num: usize,
+    ) -> Result<(WalletUnlocked, Vec<WalletUnlocked>)> {
+        match self {
+            WalletSource::Mnemonic { path } => {
+                let mnemonic = read_secret(path)?;
+                let owner =
+                    WalletUnlocked::new_from_mnemonic_phrase(&mnemonic, Some(provider.clone()))?;
+
+                let traders = (offset..offset + num)
+                    .map(|i| {
+                        let secret_key = SecretKey::new_from_mnemonic_phrase_with_path(
+                            &mnemonic,
+                            &format!("m/44'/60'/0'/{}", i),
+                        )
+                        .map_err(|e| Error::Other(format!("Cannot derive trader {}: {}", i, e)))?;
+                        Ok(WalletUnlocked::new_from_private_key(
+                            secret_key,
+                            Some(provider.clone()),
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok((owner, traders))
+            }
+            WalletSource::Keystore {
+                owner,
+                traders,
+                password_path,
+            } => {
+                let password = read_secret(password_path)?;
+                let load = |path: &Path| {
+                    WalletUnlocked::load_keystore(path, &password, Some(provider.clone()))
+                };
+
+                let owner = load(Path::new(owner))?;
+                let traders = key_files(traders, offset, num)?
+                    .iter()
+                    .map(|path| load(path))
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok((owner, traders))
+            }
+            WalletSource::PrivateKeys { owner, traders } => {
+                let load = |path: &Path| -> Result<WalletUnlocked> {
+                    let secret_key = SecretKey::from_str(&read_secret(path)?).map_err(|e| {
+                        Error::Other(format!("Invalid private key in {}: {}", path.display(), e))
+                    })?;
+                    Ok(WalletUnlocked::new_from_private_key(
+                        secret_key,
+                        Some(provider.clone()),
+                    ))
+                };
+
+                let owner = load(Path::new(owner))?;
+                let traders = key_files(traders, offset, num)?
+                    .iter()
+                    .map(|path| load(path))
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok((owner, traders))
+            }
+        }
+    }
+}
+
+/// Read a secret file without the trailing newline
+fn read_secret(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    let secret = fs::read_to_string(path)
+        .map_err(|e| Error::IO(format!("Cannot read {}: {}", path.display(), e)))?;
+
+    Ok(secret.trim().to_string())
+}
+
+/// Files of the directory sorted by name, `num` of them from `offset`
+fn key_files(dir: impl AsRef<Path>, offset: usize, num: usize) -> Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    let mut files = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    files.retain(|path| path.is_file());
+    files.sort();
+
+    if files.len() < offset + num {
+        return Err(Error::Other(format!(
+            "{} has {} keys, {} needed",
+            dir.display(),
+            files.len(),
+            offset + num
+        )));
+    }
+
+    Ok(files.into_iter().skip(offset).take(num).collect())
+}
@@ -1,22 +1,37 @@
 use fuels::{
-    accounts::{provider::Provider, wallet::WalletUnlocked},
-    crypto::SecretKey,
-    types::{ContractId, DryRunner},
+    accounts::{provider::Provider, ViewOnlyAccount},
+    types::{Address, ContractId, Identity},
 };
+use futures::future::join_all;
 use spark_market_sdk::SparkMarketContract;
-use std::{env, sync::Arc, time::Duration};
+use std::{
+    env,
+    path::Path,
+    sync::{self, Arc},
+    time::{Duration, Instant},
+};
 use tokio::{
-    sync::{mpsc::unbounded_channel, Mutex, RwLock},
+    sync::{mpsc::unbounded_channel, watch, Mutex, RwLock},
+    task::JoinHandle,
     time,
 };
+use tokio_util::sync::CancellationToken;
 
 use crate::{
     config::Config,
     error::Error,
-    operation::{OperationManager, OperationMessage},
-    orderbook::{Orderbook, OrderbookSubscriber},
-    price::PriceApi,
-    strategy::Strategy,
+    keeper::Keeper,
+    operation::{
+        AssetType, CancelOrderOperation, DeadLetterQueue, DepositOperation, GasPolicy, Operation,
+        OperationManager, OperationMessage, OperationParams, Trader, TransactionTracker,
+        WithdrawOperation,
+    },
+    orderbook::{user_orders, OrderId, Orderbook, OrderbookSubscriber},
+    price::{
+        pair_key, CircuitBreaker, PriceCache, PriceHistory, PriceService, PriceSnapshot,
+        PriceStream, PriceUpdate,
+    },
+    strategy::{Strategy, Sweeper},
     types::{Amount, Asset, Receiver, Sender},
 };
 
@@ -24,122 +39,164 @@ pub struct FillerBot {
     /// Common configuration
     pub config: Arc<Config>,
 
+    /// Latest configuration, updated on reload
+    pub config_tx: watch::Sender<Arc<Config>>,
+
     /// Orderbook for the market
     pub orderbook: Arc<RwLock<Orderbook>>,
 
     /// Last price from external API
-    pub last_external_price: Arc<RwLock<Option<u64>>>,
+    pub last_external_price: Arc<RwLock<Option<PriceSnapshot>>>,
+
+    /// Latest accepted external prices
+    pub price_history: Arc<RwLock<PriceHistory>>,
+
+    /// External prices shared by all markets
+    pub price_service: Arc<PriceService>,
+
+    /// External price stream, the API is polled when it stalls
+    pub price_stream: Option<Arc<dyn PriceStream>>,
 
-    /// External price API
-    pub price_api: Arc<dyn PriceApi>,
+    /// Guard against external price jumps
+    pub price_breaker: Arc<sync::Mutex<CircuitBreaker>>,
 
     pub base: Asset,
     pub quote: Asset,
 
-    /// Traders
-    pub traders: Vec<WalletUnlocked>,
-    pub next_trader: Arc<Mutex<usize>>,
-
     pub market_contract: Arc<RwLock<SparkMarketContract>>,
+    pub tracker: Arc<TransactionTracker>,
     pub operation_manager: Arc<OperationManager>,
     pub operation_tx: Sender<OperationMessage>,
     pub operation_rx: Receiver<OperationMessage>,
 
     pub submit_tx: Sender<bool>,
     pub submit_rx: Receiver<bool>,
+
+    /// Cancelled to stop producing new operations
+    pub shutdown: CancellationToken,
+    /// Cancelled to stop the operation pipeline once it's drained
+    pub stop: CancellationToken,
+    pub tasks: Mutex<Vec<JoinHandle<()>>>,
 }
 
 impl FillerBot {
     pub async fn new(
         market_id: ContractId,
         config: Arc<Config>,
-        price_api: Arc<dyn PriceApi>,
-    ) -> Self {
+        price_service: Arc<PriceService>,
+    ) -> Result<Self, Error> {
         log::info!("Initialiaze bot {:?}", market_id);
 
-        // TODO: Remove this hardcoded mnemonic & add multiple wallet support
-        let mnemonic = env::var("WALLET_MNEMONIC").unwrap();
         // Trader set from 0
         let trader_set = env::var("TRADER_SET").unwrap().parse::<usize>().unwrap() - 1;
         log::info!("TRADER_SET: {}", trader_set);
 
-        let provider = Provider::connect("testnet.fuel.network").await.unwrap();
+        let provider = Provider::connect("testnet.fuel.network").await?;
         // let consensus_parameters = provider.consensus_parameters();
         // log::info!("Consensus parameters: {:?}", consensus_parameters);
 
-        let wallet =
-            WalletUnlocked::new_from_mnemonic_phrase(&mnemonic, Some(provider.clone())).unwrap();
-
-        // Generate trader wallets
+        // Owner and trader wallets of the set
         let trader_offset = trader_set * config.traders_num;
-        let traders = (trader_offset..trader_offset + config.traders_num)
-            .map(|i| {
-                let secret_key = SecretKey::new_from_mnemonic_phrase_with_path(
-                    &mnemonic,
-                    &format!("m/44'/60'/0'/{}", i),
-                )
-                .unwrap();
-                WalletUnlocked::new_from_private_key(secret_key, Some(provider.clone()))
-            })
+        let (wallet, traders) = config
+            .wallet
+            .load(&provider, trader_offset, config.traders_num)?;
+        let traders = traders
+            .into_iter()
+            .map(|wallet| Trader::new(wallet, config.max_in_flight))
             .collect::<Vec<_>>();
 
         let market_contract = SparkMarketContract::new(market_id, wallet.clone()).await;
 
         // Get market base and quote assets
-        let (base, base_decimals, quote, quote_balance, ..) =
-            market_contract.config().await.unwrap().value;
+        let (base, base_decimals, quote, quote_balance, ..) = market_contract
+            .config()
+            .await
+            .map_err(|e| Error::Market(e.to_string()))?
+            .value;
 
         let orderbook = Orderbook::new();
 
         // Initialize the operation channel & manager
         let (operation_tx, operation_rx) = unbounded_channel::<OperationMessage>();
         let (submit_tx, submit_rx) = unbounded_channel::<bool>();
-        let operation_manager = OperationManager::new(config.multicall_size);
+        let tracker = Arc::new(TransactionTracker::new(
+            provider.clone(),
+            Duration::from_millis(config.tx_timeout),
+        ));
+        let operation_manager = OperationManager::new(
+            traders,
+            OperationParams::from(config.as_ref()),
+            tracker.clone(),
+            Arc::new(DeadLetterQueue::new(&config.dead_letter_path)),
+            GasPolicy::from(&config.gas),
+        );
+
+        let (config_tx, _) = watch::channel(config.clone());
+        let price_stream: Option<Arc<dyn PriceStream>> =
+            config.price.stream.as_ref().map(Arc::from);
+        let price_breaker = Arc::new(sync::Mutex::new(CircuitBreaker::from(
+            &config.price.breaker,
+        )));
+        let price_history = Arc::new(RwLock::new(PriceHistory::new(config.price.history_size)));
 
-        Self {
+        Ok(Self {
             config,
+            config_tx,
             base: Asset::new(base, base_decimals as u8),
             quote: Asset::new(quote, quote_balance as u8),
             orderbook: Arc::new(RwLock::new(orderbook)),
             last_external_price: Arc::new(RwLock::new(None)),
-            price_api,
-            traders,
-            next_trader: Arc::new(Mutex::new(0)),
+            price_history,
+            price_service,
+            price_stream,
+            price_breaker,
             market_contract: Arc::new(RwLock::new(market_contract)),
+            tracker,
             operation_manager: Arc::new(operation_manager),
             operation_tx: Arc::new(operation_tx),
             operation_rx: Arc::new(Mutex::new(operation_rx)),
             submit_tx: Arc::new(submit_tx),
             submit_rx: Arc::new(Mutex::new(submit_rx)),
-        }
+            shutdown: CancellationToken::new(),
+            stop: CancellationToken::new(),
+            tasks: Mutex::new(Vec::new()),
+        })
     }
 
     /// Run the bot with traders
     /// 1. Run orderbook subscriber
     /// 2. Start syncing external price
     /// 3. Run traders
+    /// 4. Track submitted transactions
     pub async fn run(&self) -> Result<(), Error> {
         log::info!("Running bot...");
 
         // Run orderbook subscriber
         let orderbook = self.orderbook.clone();
         let subscriber = OrderbookSubscriber::new(&self.config);
-        tokio::spawn(async move {
-            if let Err(e) = subscriber.start(orderbook).await {
+        let shutdown = self.shutdown.clone();
+        let handle = tokio::spawn(async move {
+            if let Err(e) = subscriber.start(orderbook, shutdown).await {
                 log::error!("Error while running orderbook subscriber: {}", e);
             }
         });
+        self.tasks.lock().await.push(handle);
 
         // Start syncing price
         let price_ids = (
             self.config.assets[&self.base.id].price_id.clone(),
             self.config.assets[&self.quote.id].price_id.clone(),
         );
-        self.start_sync_external_price(self.price_api.clone(), price_ids)
-            .await;
+        self.start_sync_external_price(
+            self.price_service.clone(),
+            self.price_stream.clone(),
+            price_ids,
+        )
+        .await;
 
         self.start_collect_operations().await;
         self.start_process_operations().await;
+        self.start_track_transactions().await;
 
         Ok(())
     }
@@ -148,14 +205,22 @@ impl FillerBot {
         let operation_manager = self.operation_manager.clone();
         let operation_rx = self.operation_rx.clone();
         let submit_tx = self.submit_tx.clone();
-        let multicall_size = self.config.multicall_size;
+        let stop = self.stop.clone();
 
         // Start handle operations
-        tokio::spawn(async move {
-            while let Some(message) = operation_rx.lock().await.recv().await {
+        let handle = tokio::spawn(async move {
+            loop {
+                let message = tokio::select! {
+                    _ = stop.cancelled() => break,
+                    message = async { operation_rx.lock().await.recv().await } => message,
+                };
+                let Some(message) = message else {
+                    break;
+                };
+
                 let total_operations = operation_manager.add(&message).await;
 
-                if total_operations >= multicall_size {
+                if total_operations >= operation_manager.params().multicall_size {
                     log::debug!("TOTAL: {}", total_operations);
 
                     if let Err(e) = submit_tx.send(true) {
@@ -164,6 +229,7 @@ impl FillerBot {
                 }
             }
         });
+        self.tasks.lock().await.push(handle);
     }
 
     pub async fn start_process_operations(&self) {
@@ -171,75 +237,506 @@ impl FillerBot {
 
         let operation_manager = self.operation_manager.clone();
         let market_contract = self.market_contract.clone();
-        let traders = self.traders.clone();
-        let next_trader = self.next_trader.clone();
+        let stop = self.stop.clone();
 
-        tokio::spawn(async move {
-            while submit_rx.lock().await.recv().await.is_some() {
-                let mut next_trader = next_trader.lock().await;
+        let handle = tokio::spawn(async move {
+            loop {
+                // Full multicalls are signalled, partial ones wait for the flush
+                let flush_at = operation_manager.next_flush().await;
+                let signal = tokio::select! {
+                    _ = stop.cancelled() => break,
+                    signal = async { submit_rx.lock().await.recv().await } => signal,
+                    _ = time::sleep_until(flush_at.into()) => Some(false),
+                };
+                if signal.is_none() {
+                    break;
+                }
 
-                // Select trader
-                let trader = traders[*next_trader].clone();
+                // Take batches of every ready trader, waiting while all of them are busy
+                loop {
+                    let batch = tokio::select! {
+                        _ = stop.cancelled() => return,
+                        batch = operation_manager.next_batch() => batch,
+                    };
+                    let Some(batch) = batch else {
+                        break;
+                    };
+
+                    let operation_manager = operation_manager.clone();
+                    let market_contract = market_contract.clone();
+
+                    // In-flight multicalls are drained by the trader leases on shutdown
+                    tokio::spawn(async move {
+                        operation_manager.process(batch, &market_contract).await;
+                    });
+                }
+            }
+        });
+        self.tasks.lock().await.push(handle);
+    }
 
-                // Move turn to the next trader
-                *next_trader = (*next_trader + 1) % traders.len();
+    pub async fn start_track_transactions(&self) {
+        let tracker = self.tracker.clone();
+        let operation_manager = self.operation_manager.clone();
+        let submit_tx = self.submit_tx.clone();
+        let interval = self.config.tx_status_interval;
+        let stop = self.stop.clone();
 
-                let operation_manager = operation_manager.clone();
-                let market_contract = market_contract.clone();
+        let handle = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = stop.cancelled() => break,
+                    _ = time::sleep(Duration::from_millis(interval)) => {}
+                }
 
-                tokio::spawn(async move {
-                    operation_manager.process(&trader, &market_contract).await;
-                });
+                for result in tracker.poll().await {
+                    // Failed operations are sent again with the next multicalls
+                    let total_operations = operation_manager.resolve(result).await;
+                    if total_operations >= operation_manager.params().multicall_size {
+                        if let Err(e) = submit_tx.send(true) {
+                            log::error!("{:?}", e);
+                        }
+                    }
+                }
             }
         });
+        self.tasks.lock().await.push(handle);
     }
 
     /// Start the strategy separately
     pub async fn start_strategy(&self) -> Result<(), Error> {
         log::info!("Starting strategy...");
 
-        let strategy = Strategy::new(self.base.clone(), self.quote.clone(), self.config.interval);
+        let strategy = Strategy::new(
+            self.base.clone(),
+            self.quote.clone(),
+            self.config.interval,
+            self.config.max_amount,
+            self.config.price.max_age,
+        );
+
+        let handle = strategy
+            .start(
+                self.orderbook.clone(),
+                self.last_external_price.clone(),
+                self.price_history.clone(),
+                self.operation_tx.clone(),
+                self.tracker.subscribe(),
+                self.config_tx.subscribe(),
+                self.shutdown.clone(),
+            )
+            .await;
+        self.tasks.lock().await.push(handle);
+
+        Ok(())
+    }
+
+    /// Start cancelling own orders which are expired or too far from the external price
+    pub async fn start_sweeper(&self) -> Result<(), Error> {
+        log::info!("Starting sweeper...");
 
-        strategy
+        let sweeper = Sweeper::from(self.config.as_ref());
+        let traders = self
+            .operation_manager
+            .traders
+            .iter()
+            .map(|trader| Address::from(trader.wallet.address()))
+            .collect();
+
+        let handle = sweeper
             .start(
+                traders,
+                self.market_contract.clone(),
                 self.orderbook.clone(),
                 self.last_external_price.clone(),
                 self.operation_tx.clone(),
-                self.config.max_amount,
+                self.config_tx.subscribe(),
+                self.shutdown.clone(),
             )
             .await;
+        self.tasks.lock().await.push(handle);
 
         Ok(())
     }
 
+    /// Start matching crossed orders on the book
+    pub async fn start_keeper(&self) -> Result<(), Error> {
+        log::info!("Starting keeper...");
+
+        let matcher_fee = self
+            .market_contract
+            .read()
+            .await
+            .matcher_fee()
+            .await
+            .map_err(|e| Error::Market(e.to_string()))?
+            .value;
+        log::info!("Matcher fee: {}", matcher_fee);
+
+        // Gas is paid in the base asset of the chain and compared with the fee by its price
+        let gas_asset_id = *self.tracker.provider.base_asset_id();
+        let gas_asset = self.config.assets.get(&gas_asset_id).ok_or_else(|| {
+            Error::Config(format!(
+                "Keeper needs the gas asset {} in assets",
+                gas_asset_id
+            ))
+        })?;
+        let gas_asset = (
+            Asset::new(gas_asset_id, gas_asset.decimals),
+            gas_asset.price_id.clone(),
+        );
+        let quote = (
+            self.quote.clone(),
+            self.config.assets[&self.quote.id].price_id.clone(),
+        );
+        let prices = self.price_service.subscribe(&gas_asset.1, &quote.1);
+
+        let keeper = Keeper::new(self.config.keeper.clone(), matcher_fee, gas_asset, quote);
+
+        let handle = keeper
+            .start(
+                self.orderbook.clone(),
+                self.operation_manager.clone(),
+                self.operation_tx.clone(),
+                prices,
+                self.shutdown.clone(),
+            )
+            .await;
+        self.tasks.lock().await.push(handle);
+
+        Ok(())
+    }
+
+    /// Find open orders of all traders on the market
+    pub async fn own_orders(&self) -> Result<Vec<(Address, OrderId)>, Error> {
+        let market_contract = self.market_contract.read().await;
+        let traders = self
+            .operation_manager
+            .traders
+            .iter()
+            .map(|trader| Address::from(trader.wallet.address()));
+
+        user_orders(&market_contract, traders).await
+    }
+
+    /// Cancel all open orders of the traders and submit cancels right away
+    pub async fn cancel_all_orders(&self) -> Result<usize, Error> {
+        let orders = self.own_orders().await?;
+        log::info!("Cancelling {} own orders...", orders.len());
+
+        for (trader, order_id) in &orders {
+            let message = OperationMessage {
+                operation: Operation::CancelOrder(CancelOrderOperation {
+                    order_id: order_id.clone(),
+                }),
+                trader: Some(*trader),
+            };
+            self.operation_manager.add(&message).await;
+        }
+
+        // Don't wait for full multicalls
+        if let Err(e) = self.submit_tx.send(true) {
+            log::error!("{:?}", e);
+        }
+
+        Ok(orders.len())
+    }
+
+    /// Deposit wallet balances of the market assets of all traders and submit deposits right away
+    pub async fn deposit_all(&self) -> Result<usize, Error> {
+        let base_asset_id = *self.tracker.provider.base_asset_id();
+        let mut deposits = 0;
+
+        for trader in &self.operation_manager.traders {
+            for asset in [&self.base, &self.quote] {
+                // Gas is paid from the wallet
+                if asset.id == base_asset_id {
+                    continue;
+                }
+
+                let balance = trader.wallet.get_asset_balance(&asset.id).await?;
+                if balance == 0 {
+                    continue;
+                }
+
+                let message = OperationMessage {
+                    operation: Operation::Deposit(DepositOperation {
+                        asset: asset.clone(),
+                        amount: Amount::new(balance),
+                    }),
+                    trader: Some(Address::from(trader.wallet.address())),
+                };
+                self.operation_manager.add(&message).await;
+                deposits += 1;
+            }
+        }
+        log::info!("Depositing {} trader balances...", deposits);
+
+        if let Err(e) = self.submit_tx.send(true) {
+            log::error!("{:?}", e);
+        }
+
+        Ok(deposits)
+    }
+
+    /// Withdraw liquid market balances of all traders and submit withdrawals right away
+    pub async fn withdraw_all(&self) -> Result<usize, Error> {
+        let mut withdrawals = 0;
+
+        for trader in &self.operation_manager.traders {
+            let address = Address::from(trader.wallet.address());
+            let account = self
+                .market_contract
+                .read()
+                .await
+                .account(Identity::Address(address))
+                .await
+                .map_err(|e| Error::Market(e.to_string()))?
+                .value;
+
+            for (asset_type, balance) in [
+                (AssetType::Base, account.liquid.base),
+                (AssetType::Quote, account.liquid.quote),
+            ] {
+                if balance == 0 {
+                    continue;
+                }
+
+                let message = OperationMessage {
+                    operation: Operation::Withdraw(WithdrawOperation {
+                        asset_type,
+                        amount: Amount::new(balance),
+                    }),
+                    trader: Some(address),
+                };
+                self.operation_manager.add(&message).await;
+                withdrawals += 1;
+            }
+        }
+        log::info!("Withdrawing {} trader balances...", withdrawals);
+
+        if let Err(e) = self.submit_tx.send(true) {
+            log::error!("{:?}", e);
+        }
+
+        Ok(withdrawals)
+    }
+
+    /// Wait until all queued operations are submitted and resolved
+    pub async fn wait_idle(&self, timeout: Duration) -> bool {
+        let wait = async {
+            while !self.operation_manager.is_idle().await {
+                time::sleep(Duration::from_millis(self.config.tx_status_interval)).await;
+            }
+        };
+
+        time::timeout(timeout, wait).await.is_ok()
+    }
+
+    /// Reload the config file and swap strategy and operation parameters live.
+    /// Changes which require a restart are rejected as a whole.
+    pub fn reload_config(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        log::info!("Reloading config...");
+        let config = Config::load(path)?;
+
+        if config == **self.config_tx.borrow() {
+            return Ok(());
+        }
+        self.config.validate_reload(&config)?;
+
+        self.operation_manager
+            .reload(OperationParams::from(&config));
+        self.config_tx.send_replace(Arc::new(config));
+
+        Ok(())
+    }
+
+    /// Accept the external price move which tripped the breaker
+    pub fn acknowledge_price(&self) {
+        log::info!("Acknowledging external price...");
+        self.price_breaker.lock().unwrap().acknowledge();
+    }
+
     pub async fn stop_strategy(&self) -> Result<(), Error> {
         todo!();
     }
 
+    /// Stop the bot gracefully
+    /// 1. Stop producing new operations and drop queued new orders
+    /// 2. Cancel own orders if configured, once in-flight ones are resolved
+    /// 3. Drain queued and in-flight multicalls
+    /// 4. Withdraw market balances if configured, once the cancels are resolved
+    /// 5. Stop the operation pipeline
+    pub async fn shutdown(&self, timeout: Duration) -> bool {
+        log::info!("Shutting down bot...");
+
+        // All steps share the timeout, a tenth of it is left to stop the tasks
+        let deadline = Instant::now() + timeout;
+        let drain_deadline = deadline - timeout / 10;
+        let remaining = |deadline: Instant| deadline.saturating_duration_since(Instant::now());
+
+        self.shutdown.cancel();
+        self.operation_manager.close().await;
+
+        if self.config.cancel_on_shutdown {
+            // Orders opened by in-flight multicalls must land before they are listed
+            if let Err(e) = self.submit_tx.send(true) {
+                log::error!("{:?}", e);
+            }
+            if !self.wait_idle(remaining(drain_deadline)).await {
+                log::warn!("In-flight operations were not resolved in time");
+            }
+
+            if let Err(e) = self.cancel_all_orders().await {
+                log::error!("Error while cancelling orders: {}", e);
+            }
+        }
+
+        // Submit the rest of operations without waiting for full multicalls
+        if let Err(e) = self.submit_tx.send(true) {
+            log::error!("{:?}", e);
+        }
+
+        let mut is_drained = self.wait_idle(remaining(drain_deadline)).await;
+
+        // Funds of the cancelled orders are liquid only after the cancels land
+        if self.config.withdraw_on_shutdown && is_drained {
+            match self.withdraw_all().await {
+                Ok(_) => is_drained = self.wait_idle(remaining(drain_deadline)).await,
+                Err(e) => log::error!("Error while withdrawing balances: {}", e),
+            }
+        }
+
+        if !is_drained {
+            log::warn!("Operations were not drained in {:?}", timeout);
+        }
+
+        self.stop.cancel();
+        let tasks = self.tasks.lock().await.drain(..).collect::<Vec<_>>();
+        if time::timeout(remaining(deadline), join_all(tasks))
+            .await
+            .is_err()
+        {
+            log::warn!("Background tasks were not stopped in {:?}", timeout);
+        }
+
+        is_drained
+    }
+
     pub async fn start_sync_external_price(
         &self,
-        price_api: Arc<dyn PriceApi>,
+        price_service: Arc<PriceService>,
+        price_stream: Option<Arc<dyn PriceStream>>,
         ids: (String, String),
     ) {
         let decimals = self.quote.decimals;
         let last_external_price = self.last_external_price.clone();
+        let price_history = self.price_history.clone();
+        let orderbook = self.orderbook.clone();
+        let breaker = self.price_breaker.clone();
+        let shutdown = self.shutdown.clone();
+        let stream_timeout = Duration::from_millis(self.config.price.stream_timeout);
+        let (updates_tx, mut updates_rx) = unbounded_channel::<PriceUpdate>();
+
+        // Stream prices as they arrive, reconnecting on errors
+        if let Some(price_stream) = price_stream {
+            // The pair is streamed directly when the stream maps it
+            let ids = [ids.0.clone(), ids.1.clone(), pair_key(&ids.0, &ids.1)];
+            let shutdown = shutdown.clone();
+            let updates_tx = Arc::new(updates_tx);
+
+            let handle = tokio::spawn(async move {
+                loop {
+                    tokio::select! {
+                        _ = shutdown.cancelled() => break,
+                        res = price_stream.stream(&[&ids[0], &ids[1], &ids[2]], updates_tx.clone()) => {
+                            if let Err(e) = res {
+                                log::error!("Error while streaming {} prices: {}", price_stream.name(), e);
+                            }
+                        }
+                    }
+
+                    // Reconnecting delay
+                    tokio::select! {
+                        _ = shutdown.cancelled() => break,
+                        _ = time::sleep(Duration::from_secs(5)) => {}
+                    }
+                }
+            });
+            self.tasks.lock().await.push(handle);
+        }
+
+        let mut cache_rx = price_service.subscribe(&ids.0, &ids.1);
+
+        let handle = tokio::spawn(async move {
+            // Streamed prices of both assets in usd and of the pair itself
+            let mut legs: [Option<PriceSnapshot<f64>>; 3] = [None, None, None];
+            let mut cache = cache_rx.borrow().clone();
+            let pair = pair_key(&ids.0, &ids.1);
+
+            // Price of the pair from the fresh streams, USD prices are divided
+            // only when neither the stream nor the polled prices have the pair directly
+            let streamed_price = |legs: &[Option<PriceSnapshot<f64>>; 3], cache: &PriceCache| {
+                let fresh = |index: usize| legs[index].filter(|leg| leg.is_fresh(stream_timeout));
+                if let Some(price) = fresh(2) {
+                    return Some(price);
+                }
+                if cache.pairs.contains_key(&pair) {
+                    return None;
+                }
+                Some(fresh(0)?.cross(&fresh(1)?))
+            };
 
-        tokio::spawn(async move {
             loop {
-                // Get prices for both assets in usd
-                let prices = price_api.prices(&[&ids.0, &ids.1]).await.unwrap();
-
-                // Lock and unlock before sleep
-                {
-                    let mut price = last_external_price.write().await;
-                    // Calculate the price of the base asset in terms of the quote asset
-                    *price = Some(*Amount::from_readable(prices[0] / prices[1], decimals));
-                    log::info!("EXTERNAL PRICE: {:?}", price);
+                // Price of the base asset in terms of the quote asset
+                let pair_price = tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    Some(update) = updates_rx.recv() => {
+                        legs[update.index] = Some(update.price);
+
+                        match streamed_price(&legs, &cache) {
+                            Some(price) => price,
+                            None => continue,
+                        }
+                    }
+                    Ok(()) = cache_rx.changed() => {
+                        cache = cache_rx.borrow_and_update().clone();
+
+                        // Use the polled prices only when the stream is missing or stalled,
+                        // or to confirm the move which tripped the breaker
+                        let is_tripped = breaker.lock().unwrap().is_tripped();
+                        if !is_tripped && streamed_price(&legs, &cache).is_some() {
+                            continue;
+                        }
+
+                        match cache.price(&ids.0, &ids.1) {
+                            Some(price) => price,
+                            None => {
+                                // Stop quoting until the price recovers
+                                if last_external_price.write().await.take().is_some() {
+                                    log::warn!("External price is unavailable");
+                                }
+                                continue;
+                            }
+                        }
+                    }
+                    else => break,
+                };
+                let snapshot = pair_price.map(|value| *Amount::from_readable(value, decimals));
+
+                // Strategies are paused while the breaker is tripped
+                let mid = orderbook.read().await.mid_price();
+                let is_accepted = breaker.lock().unwrap().check(&snapshot, mid);
+
+                if is_accepted {
+                    price_history.write().await.push(snapshot);
                 }
 
-                // TODO: Sync price every 5 seconds (update when change to pro plan)
-                time::sleep(Duration::from_secs(5)).await;
+                let mut price = last_external_price.write().await;
+                *price = is_accepted.then_some(snapshot);
+                log::debug!("EXTERNAL PRICE: {:?}", price);
             }
         });
+        self.tasks.lock().await.push(handle);
     }
 }
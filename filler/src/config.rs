@@ -1,12 +1,14 @@
 use fuels::types::{AssetId, ContractId};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use std::{collections::HashMap, fs::File, io::BufReader, path::Path};
+use std::{collections::HashMap, fs::File, io::BufReader, path::Path, time::SystemTime};
 
-use crate::error::Error;
+use filler_wallet::WalletSource;
+
+use crate::{error::Error, operation::TipPolicy, price::CoingeckoPlan};
 
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AssetConfig {
     /// Asset name (BTC, ETH, etc.)
     pub name: String,
@@ -18,14 +20,165 @@ pub struct AssetConfig {
     pub price_id: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// Attempts after which an operation goes to the dead-letter queue
+    pub max_attempts: u32,
+
+    /// Initial retry delay in milliseconds
+    pub base_delay: u64,
+
+    /// Maximum retry delay in milliseconds
+    pub max_delay: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GasConfig {
+    /// Initial gas estimate per call, used until the first dry-run
+    pub gas_per_call: u64,
+
+    /// Initial fee estimate per call, used to select coins until the first dry-run
+    pub fee_per_call: u64,
+
+    /// Relative margin added to the estimated gas (0.2 = 20%)
+    pub tolerance: f64,
+
+    /// Tip policy for multicall transactions
+    pub tip: TipPolicy,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PriceProviderConfig {
+    /// Coingecko API of the plan, the key is taken from `COINGECKO_API_KEY`.
+    /// Quote price ids mapped to vs currencies are priced directly.
+    Coingecko {
+        plan: CoingeckoPlan,
+        /// Request timeout in milliseconds
+        timeout: u64,
+        #[serde(default)]
+        vs_currencies: HashMap<String, String>,
+    },
+    /// Binance spot API, price ids mapped to USDT symbols and pairs to native symbols
+    Binance {
+        host: String,
+        symbols: HashMap<String, String>,
+        /// Request timeout in milliseconds
+        timeout: u64,
+    },
+    /// Pyth Hermes, price ids mapped to USD price feed ids
+    Pyth {
+        host: String,
+        feeds: HashMap<String, String>,
+        /// Request timeout in milliseconds
+        timeout: u64,
+    },
+    /// Pyth oracle contract on Fuel, price ids mapped to USD price feed ids
+    PythOracle {
+        node_url: String,
+        contract_id: ContractId,
+        feeds: HashMap<String, String>,
+        /// Time in milliseconds after which an oracle price is rejected
+        max_age: u64,
+    },
+    /// Kraken API, price ids mapped to USD pairs and pairs to native pairs
+    Kraken {
+        host: String,
+        pairs: HashMap<String, String>,
+        /// Request timeout in milliseconds
+        timeout: u64,
+    },
+    /// Static USD prices by price ids
+    Fixed { prices: HashMap<String, f64> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PriceStreamConfig {
+    /// Binance book ticker streams, price ids mapped to USDT symbols
+    /// and pairs mapped to native symbols
+    Binance {
+        host: String,
+        symbols: HashMap<String, String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BreakerConfig {
+    /// Maximum relative jump from the last accepted price
+    pub max_jump: f64,
+
+    /// Maximum relative distance from the on-chain mid price
+    pub max_mid_deviation: f64,
+
+    /// Number of agreeing providers of a later price which confirms a tripped move.
+    /// Streamed prices come from a single provider, with more than 1 they are confirmed
+    /// by the polled prices.
+    pub min_confirmations: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceConfig {
+    /// Providers queried concurrently for every price
+    pub providers: Vec<PriceProviderConfig>,
+
+    /// Websocket price stream, providers are polled only when it stalls
+    pub stream: Option<PriceStreamConfig>,
+
+    /// Time in milliseconds without stream updates before falling back to polling
+    pub stream_timeout: u64,
+
+    /// Budget of price API requests shared by all markets
+    pub requests_per_minute: u32,
+
+    /// Time in milliseconds after which the price is treated as missing
+    pub max_age: u64,
+
+    /// Number of accepted prices kept per market for volatility and TWAP estimates
+    pub history_size: usize,
+
+    /// Guard against price jumps, pauses strategies until the move is confirmed
+    pub breaker: BreakerConfig,
+
+    /// Initial delay in milliseconds before polling again after a failure
+    pub retry_base_delay: u64,
+
+    /// Maximum delay in milliseconds between polls after failures
+    pub retry_max_delay: u64,
+
+    /// Maximum relative distance from the median before a provider price is discarded
+    pub max_deviation: f64,
+
+    /// Minimum number of agreeing providers to accept the price
+    pub min_sources: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeeperConfig {
+    /// Match crossed orders on the book
+    pub enabled: bool,
+
+    /// Keeper interval in milliseconds
+    pub interval: u64,
+
+    /// Time in milliseconds before the same pair can be matched again
+    pub cooldown: u64,
+
+    /// Maximum number of pairs matched per interval
+    pub max_pairs: usize,
+
+    /// Maximum transaction fee paid per match call
+    pub max_fee_per_match: u64,
+}
+
 #[serde_as]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Config {
     /// Indexer websocket host url
     pub indexer_ws_host: String,
 
-    /// Coingecko API host url
-    pub coingecko_host: String,
+    /// External price providers and aggregation
+    pub price: PriceConfig,
 
     /// Maximum amount to trade
     pub max_amount: f64,
@@ -33,12 +186,76 @@ pub struct Config {
     /// Strategy interval in milliseconds
     pub interval: u64,
 
+    /// Time in seconds after which own orders are cancelled
+    pub order_ttl: u64,
+
+    /// Maximum relative distance from the external price before own orders are cancelled
+    pub max_price_deviation: f64,
+
+    /// Stale orders check interval in milliseconds
+    pub sweep_interval: u64,
+
     /// Number of traders to run
     pub traders_num: usize,
 
+    /// Owner and trader keys
+    pub wallet: WalletSource,
+
     /// Maximum number of calls in multicall transaction
     pub multicall_size: usize,
 
+    /// Time in milliseconds a partial multicall waits for more operations before submission
+    pub batch_delay: u64,
+
+    /// Maximum number of unresolved transactions per trader
+    pub max_in_flight: usize,
+
+    /// Cancel orders of the traders left from the previous run
+    pub cancel_on_startup: bool,
+
+    /// Cancel all orders of the traders before exit
+    pub cancel_on_shutdown: bool,
+
+    /// Time in milliseconds to wait for the cancels to be resolved
+    pub cancel_timeout: u64,
+
+    /// Deposit wallet balances of the market assets of the traders on startup,
+    /// the base asset of the chain is left for gas
+    pub deposit_on_startup: bool,
+
+    /// Time in milliseconds to wait for the startup deposits to be resolved
+    pub deposit_timeout: u64,
+
+    /// Withdraw market balances of the traders before exit, once the cancels are resolved
+    pub withdraw_on_shutdown: bool,
+
+    /// Time in milliseconds for the whole shutdown, draining operations and stopping tasks
+    pub shutdown_timeout: u64,
+
+    /// Config file check interval in milliseconds
+    pub reload_interval: u64,
+
+    /// Transaction status polling interval in milliseconds
+    pub tx_status_interval: u64,
+
+    /// Time in milliseconds after which a pending transaction is considered lost
+    pub tx_timeout: u64,
+
+    /// Gas limit and tip settings for multicall transactions
+    pub gas: GasConfig,
+
+    /// Simulate every multicall before submission and drop the operations which revert
+    pub dry_run: bool,
+
+    /// Retry policy for failed operations
+    pub retry: RetryConfig,
+
+    /// File to store operations that failed permanently
+    pub dead_letter_path: String,
+
+    /// Matching of crossed orders
+    pub keeper: KeeperConfig,
+
     /// Spark Market contract IDs
     pub markets: Vec<ContractId>,
 
@@ -52,7 +269,121 @@ impl Config {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
         let config: Config = serde_json::from_reader(reader)?;
+        config.validate()?;
 
         Ok(config)
     }
+
+    /// Last modification time of the config file
+    pub fn modified(path: impl AsRef<Path>) -> Result<SystemTime, Error> {
+        Ok(path.as_ref().metadata()?.modified()?)
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.max_amount <= 0.0 {
+            return Err(Error::Config("max_amount must be positive".into()));
+        }
+        if self.interval == 0
+            || self.sweep_interval == 0
+            || self.reload_interval == 0
+            || self.batch_delay == 0
+        {
+            return Err(Error::Config("intervals must be positive".into()));
+        }
+        if self.max_price_deviation <= 0.0 {
+            return Err(Error::Config("max_price_deviation must be positive".into()));
+        }
+        if self.multicall_size == 0 {
+            return Err(Error::Config("multicall_size must be positive".into()));
+        }
+        if self.price.requests_per_minute == 0 {
+            return Err(Error::Config(
+                "price requests_per_minute must be positive".into(),
+            ));
+        }
+        if self.price.min_sources == 0 || self.price.min_sources > self.price.providers.len() {
+            return Err(Error::Config(
+                "price min_sources must be between 1 and the number of providers".into(),
+            ));
+        }
+        if self.price.breaker.min_confirmations == 0
+            || self.price.breaker.min_confirmations > self.price.providers.len()
+        {
+            return Err(Error::Config(
+                "breaker min_confirmations must be between 1 and the number of providers".into(),
+            ));
+        }
+        if self.retry.base_delay > self.retry.max_delay {
+            return Err(Error::Config(
+                "retry base_delay must not exceed max_delay".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Check that the reloaded config changes only strategy and operation parameters.
+    /// Markets, assets, wallets and connections are fixed until restart.
+    pub fn validate_reload(&self, config: &Config) -> Result<(), Error> {
+        config.validate()?;
+
+        let fixed = Config {
+            max_amount: self.max_amount,
+            interval: self.interval,
+            order_ttl: self.order_ttl,
+            max_price_deviation: self.max_price_deviation,
+            sweep_interval: self.sweep_interval,
+            multicall_size: self.multicall_size,
+            batch_delay: self.batch_delay,
+            dry_run: self.dry_run,
+            retry: self.retry.clone(),
+            ..config.clone()
+        };
+        if fixed != *self {
+            return Err(Error::Config(
+                "only strategy, sweeper, multicall, dry-run and retry parameters can be reloaded"
+                    .into(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        Config::load(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../config.testnet.json"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn reloads_strategy_and_retry() {
+        let current = config();
+        let mut reloaded = current.clone();
+        reloaded.max_amount *= 2.0;
+        reloaded.interval += 1000;
+        reloaded.dry_run = !reloaded.dry_run;
+        reloaded.retry.max_attempts += 1;
+
+        assert!(current.validate_reload(&reloaded).is_ok());
+    }
+
+    #[test]
+    fn rejects_reload_of_markets() {
+        let current = config();
+        let mut reloaded = current.clone();
+        reloaded.markets.push(ContractId::zeroed());
+        reloaded.max_amount *= 2.0;
+
+        assert!(matches!(
+            current.validate_reload(&reloaded),
+            Err(Error::Config(_))
+        ));
+    }
 }
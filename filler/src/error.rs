@@ -25,4 +25,10 @@ pub enum Error {
 
     #[error("Price API: {0}")]
     PriceApi(String),
+
+    #[error("Market: {0}")]
+    Market(String),
+
+    #[error("Config: {0}")]
+    Config(String),
 }
@@ -0,0 +1,135 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{watch, RwLock},
+    task::JoinHandle,
+    time,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::KeeperConfig,
+    operation::{MatchOrdersOperation, Operation, OperationManager, OperationMessage},
+    orderbook::{OrderId, Orderbook},
+    price::PriceCache,
+    types::{Amount, Asset, Sender},
+};
+
+/// Matches crossed orders on the book as a service to the market
+#[derive(Debug, Clone)]
+pub struct Keeper {
+    pub config: KeeperConfig,
+    /// Fee received by the matcher per matched order, in the quote asset
+    /// as set in the market contract
+    pub matcher_fee: u64,
+    /// Base asset of the chain which pays for gas, with its price id
+    pub gas_asset: (Asset, String),
+    /// Quote asset of the market, with its price id
+    pub quote: (Asset, String),
+}
+
+impl Keeper {
+    pub fn new(
+        config: KeeperConfig,
+        matcher_fee: u64,
+        gas_asset: (Asset, String),
+        quote: (Asset, String),
+    ) -> Self {
+        Self {
+            config,
+            matcher_fee,
+            gas_asset,
+            quote,
+        }
+    }
+
+    /// Gas fee converted to the quote asset, `None` while the price is unknown
+    pub fn fee_in_quote(&self, fee: u64, prices: &PriceCache) -> Option<u64> {
+        let (gas_asset, gas_price_id) = &self.gas_asset;
+        let (quote, quote_price_id) = &self.quote;
+
+        let price = prices.price(gas_price_id, quote_price_id)?;
+        let fee = Amount::new(fee).to_readable(gas_asset.decimals) * price.value;
+        Some(*Amount::from_readable(fee, quote.decimals))
+    }
+
+    /// Run the keeper
+    pub async fn start(
+        &self,
+        orderbook: Arc<RwLock<Orderbook>>,
+        operation_manager: Arc<OperationManager>,
+        operation_tx: Sender<OperationMessage>,
+        prices: watch::Receiver<Arc<PriceCache>>,
+        shutdown: CancellationToken,
+    ) -> JoinHandle<()> {
+        let keeper = self.clone();
+        let config = self.config.clone();
+
+        tokio::spawn(async move {
+            // Pairs sent recently, so they are not matched twice while in flight
+            let mut submitted = HashMap::<(OrderId, OrderId), Instant>::new();
+            let cooldown = Duration::from_millis(config.cooldown);
+
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = time::sleep(Duration::from_millis(config.interval)) => {}
+                }
+                submitted.retain(|_, at| at.elapsed() < cooldown);
+
+                // Matcher gets the fee for both orders of the pair,
+                // the gas fee is compared with it in the quote asset
+                let fee = operation_manager.gas_policy.fee_per_call();
+                let fee_in_quote = keeper.fee_in_quote(fee, &prices.borrow());
+                let income = keeper.matcher_fee.saturating_mul(2);
+                if fee > config.max_fee_per_match
+                    || fee_in_quote.map_or(true, |fee_in_quote| fee_in_quote >= income)
+                {
+                    log::debug!(
+                        "Matching is not profitable, fee: {} ({:?} in quote), matcher fee: {}",
+                        fee,
+                        fee_in_quote,
+                        income
+                    );
+                    continue;
+                }
+
+                let pairs = {
+                    let orderbook = orderbook.read().await;
+                    orderbook
+                        .crossed_pairs()
+                        .into_iter()
+                        .map(|(buy, sell)| (buy.id.clone(), sell.id.clone()))
+                        .filter(|pair| !submitted.contains_key(pair))
+                        .take(config.max_pairs)
+                        .collect::<Vec<_>>()
+                };
+
+                if operation_tx.is_closed() {
+                    log::info!("Operation channel closed, stopping keeper...");
+                    break;
+                }
+
+                for (buy, sell) in pairs {
+                    log::info!("MATCH: {} <> {}", buy, sell);
+
+                    let message = OperationMessage {
+                        operation: Operation::MatchOrders(MatchOrdersOperation {
+                            orders: vec![buy.clone(), sell.clone()],
+                        }),
+                        trader: None,
+                    };
+
+                    if let Err(e) = operation_tx.send(message) {
+                        log::error!("Error sending operation: {:?}", e);
+                        continue;
+                    }
+                    submitted.insert((buy, sell), Instant::now());
+                }
+            }
+        })
+    }
+}
@@ -1,30 +1,38 @@
 use dotenv::dotenv;
 use price::PriceApi;
-use std::{env, sync::Arc};
-use tokio::signal::unix::{signal, SignalKind};
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    time,
+};
+use tokio_util::sync::CancellationToken;
 
-use crate::{bot::FillerBot, config::Config, price::CoingeckoApi};
+use crate::{
+    bot::FillerBot,
+    config::Config,
+    price::{AggregatedPriceApi, PriceService},
+};
 
 mod bot;
 mod config;
 mod error;
+mod keeper;
 mod operation;
 mod orderbook;
 mod price;
 mod strategy;
 mod types;
 
+const CONFIG_PATH: &str = "config.testnet.json";
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv().ok();
     env_logger::init();
 
     log::info!("Starting instance...");
-    let config = Config::load("config.testnet.json")?;
-    let price_api = CoingeckoApi::new(
-        config.coingecko_host.clone(),
-        env::var("COINGECKO_API_KEY").unwrap(),
-    );
+    let config = Config::load(CONFIG_PATH)?;
+    let price_api = AggregatedPriceApi::from_config(&config)?;
 
     let markets = config.markets.clone();
 
@@ -32,24 +40,91 @@ async fn main() -> anyhow::Result<()> {
     let config = Arc::new(config);
     let price_api: Arc<dyn PriceApi> = Arc::new(price_api);
 
+    // Prices are fetched once for all markets
+    let price_service = Arc::new(PriceService::new(price_api, &config));
+    let price_shutdown = CancellationToken::new();
+    let price_handle = price_service.clone().start(price_shutdown.clone());
+
     // Create bots per each market
-    let bot = FillerBot::new(markets[0], config.clone(), price_api.clone()).await;
+    let bot = FillerBot::new(markets[0], config.clone(), price_service.clone()).await?;
 
     // Run bot without strategy
     bot.run().await?;
 
+    // Orders of the previous run have no owner in memory
+    if config.cancel_on_startup {
+        bot.cancel_all_orders().await?;
+        bot.wait_idle(Duration::from_millis(config.cancel_timeout))
+            .await;
+    }
+
+    // Orders are opened from the market balances of the traders
+    if config.deposit_on_startup {
+        bot.deposit_all().await?;
+        bot.wait_idle(Duration::from_millis(config.deposit_timeout))
+            .await;
+    }
+
     // TODO: You can run different strategies by api
     bot.start_strategy().await?;
+    bot.start_sweeper().await?;
+
+    if config.keeper.enabled {
+        bot.start_keeper().await?;
+    }
 
     // ---------------------------------------------------
 
-    let mut sigint = signal(SignalKind::interrupt()).unwrap();
-    let mut sigterm = signal(SignalKind::interrupt()).unwrap();
+    let mut sigint = signal(SignalKind::interrupt())?;
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sighup = signal(SignalKind::hangup())?;
+    let mut sigusr1 = signal(SignalKind::user_defined1())?;
+
+    // Reload the config on SIGHUP or when the file is modified
+    let mut modified = Config::modified(CONFIG_PATH).ok();
+    let mut reload_interval = time::interval(Duration::from_millis(config.reload_interval));
 
-    tokio::select! {
-        _ = sigint.recv() => log::info!("Received signal SIGINT. Shutting down."),
-        _ = sigterm.recv() => log::info!("Received signal SIGTERM. Shutting down."),
+    loop {
+        tokio::select! {
+            _ = sigint.recv() => {
+                log::info!("Received signal SIGINT. Shutting down.");
+                break;
+            }
+            _ = sigterm.recv() => {
+                log::info!("Received signal SIGTERM. Shutting down.");
+                break;
+            }
+            _ = sighup.recv() => {
+                log::info!("Received signal SIGHUP. Reloading config.");
+                if let Err(e) = bot.reload_config(CONFIG_PATH) {
+                    log::error!("Error while reloading config: {}", e);
+                }
+            }
+            _ = sigusr1.recv() => {
+                log::info!("Received signal SIGUSR1. Acknowledging price move.");
+                bot.acknowledge_price();
+            }
+            _ = reload_interval.tick() => {
+                let last_modified = Config::modified(CONFIG_PATH).ok();
+                if last_modified != modified {
+                    modified = last_modified;
+                    if let Err(e) = bot.reload_config(CONFIG_PATH) {
+                        log::error!("Error while reloading config: {}", e);
+                    }
+                }
+            }
+        }
     }
 
+    if !bot
+        .shutdown(Duration::from_millis(config.shutdown_timeout))
+        .await
+    {
+        log::warn!("Not all operations were resolved before shutdown");
+    }
+
+    price_shutdown.cancel();
+    price_handle.await?;
+
     Ok(())
 }
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, ErrorKind},
+    path::{Path, PathBuf},
+};
+use tokio::sync::Mutex;
+
+use super::Operation;
+use crate::error::Error;
+
+/// Operation which is not going to be submitted anymore
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub operation: Operation,
+    pub attempts: u32,
+    pub error: String,
+    /// Unix timestamp in seconds
+    pub timestamp: i64,
+}
+
+/// Failed operations stored for inspection from the CLI
+pub struct DeadLetterQueue {
+    pub path: PathBuf,
+    pub letters: Mutex<Vec<DeadLetter>>,
+}
+
+impl DeadLetterQueue {
+    /// Open the queue, keeping the letters of the previous runs
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let letters = match Self::load(&path) {
+            Ok(letters) => letters,
+            Err(e) => {
+                log::error!("Error while loading dead letters: {}", e);
+                Vec::new()
+            }
+        };
+
+        Self {
+            path,
+            letters: Mutex::new(letters),
+        }
+    }
+
+    fn load(path: &Path) -> Result<Vec<DeadLetter>, Error> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    pub async fn push(&self, operation: Operation, attempts: u32, error: String) {
+        log::error!(
+            "DEAD LETTER: {:?} after {} attempts: {}",
+            operation,
+            attempts,
+            error
+        );
+
+        let mut letters = self.letters.lock().await;
+        letters.push(DeadLetter {
+            operation,
+            attempts,
+            error,
+            timestamp: chrono::Utc::now().timestamp(),
+        });
+
+        if let Err(e) = self.save(&letters) {
+            log::error!("Error while saving dead letters: {}", e);
+        }
+    }
+
+    fn save(&self, letters: &[DeadLetter]) -> Result<(), Error> {
+        let file = File::create(&self.path)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), letters)?;
+
+        Ok(())
+    }
+}
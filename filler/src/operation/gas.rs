@@ -0,0 +1,115 @@
+use fuels::{
+    accounts::wallet::WalletUnlocked,
+    programs::calls::{CallHandler, ContractCall},
+    types::errors::Error as FuelError,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::ErrorKind;
+use crate::config::GasConfig;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TipPolicy {
+    /// The same tip for every transaction
+    Fixed { tip: u64 },
+    /// Tip grows with each retry of the operations up to the cap
+    Escalating { base: u64, step: u64, max: u64 },
+}
+
+impl TipPolicy {
+    pub fn tip(&self, attempts: u32) -> u64 {
+        match *self {
+            TipPolicy::Fixed { tip } => tip,
+            TipPolicy::Escalating { base, step, max } => cmp::min(
+                base.saturating_add(step.saturating_mul(attempts as u64)),
+                max,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GasEstimate {
+    pub gas_limit: u64,
+    /// Expected fee without the tip
+    pub fee: u64,
+}
+
+/// Sizes gas limit of the multicall to the calls it actually contains
+pub struct GasPolicy {
+    /// Relative margin added to the estimated gas
+    pub tolerance: f64,
+    pub tip: TipPolicy,
+
+    /// Last known gas per call, used when estimation is not available
+    gas_per_call: AtomicU64,
+    /// Last known fee per call, used when estimation is not available
+    fee_per_call: AtomicU64,
+}
+
+impl From<&GasConfig> for GasPolicy {
+    fn from(config: &GasConfig) -> Self {
+        Self {
+            tolerance: config.tolerance,
+            tip: config.tip.clone(),
+            gas_per_call: AtomicU64::new(config.gas_per_call),
+            fee_per_call: AtomicU64::new(config.fee_per_call),
+        }
+    }
+}
+
+impl GasPolicy {
+    /// Last known fee of a single call
+    pub fn fee_per_call(&self) -> u64 {
+        self.fee_per_call.load(Ordering::Relaxed)
+    }
+
+    /// Estimate script gas limit and fee for the multicall via dry-run.
+    /// Reverts are not detected here, the estimate of a reverting multicall may succeed.
+    pub async fn estimate(
+        &self,
+        multicall: &CallHandler<WalletUnlocked, Vec<ContractCall>, ()>,
+        calls: usize,
+    ) -> Result<GasEstimate, FuelError> {
+        let calls = cmp::max(calls, 1) as u64;
+
+        match multicall
+            .estimate_transaction_cost(Some(self.tolerance), None)
+            .await
+        {
+            Ok(cost) => {
+                // Gas used already includes the tolerance
+                self.gas_per_call
+                    .store(cost.gas_used / calls, Ordering::Relaxed);
+                self.fee_per_call
+                    .store(cost.total_fee / calls, Ordering::Relaxed);
+                log::debug!("GAS: {} for {} calls", cost.gas_used, calls);
+
+                Ok(GasEstimate {
+                    gas_limit: cost.gas_used,
+                    fee: cost.total_fee,
+                })
+            }
+            Err(e) if ErrorKind::classify(&e) == ErrorKind::Fatal => Err(e),
+            Err(e) => {
+                log::warn!("Error while estimating gas: {}", e);
+                Ok(self.fallback(calls as usize))
+            }
+        }
+    }
+
+    /// Estimate from the last known costs per call
+    pub fn fallback(&self, calls: usize) -> GasEstimate {
+        let calls = cmp::max(calls, 1) as u64;
+
+        GasEstimate {
+            gas_limit: self.gas_per_call.load(Ordering::Relaxed) * calls,
+            fee: self.fee_per_call.load(Ordering::Relaxed) * calls,
+        }
+    }
+}
@@ -0,0 +1,659 @@
+use fuels::{
+    accounts::{wallet::WalletUnlocked, Account},
+    prelude::VariableOutputPolicy,
+    programs::calls::{CallHandler, CallParameters, ContractCall},
+    tx::Output,
+    types::{
+        errors::Error as FuelError,
+        input::Input,
+        transaction::{ScriptTransaction, TxPolicies},
+        transaction_builders::TransactionBuilder,
+        tx_status::TxStatus,
+        Address, Bits256,
+    },
+};
+use futures::future::{BoxFuture, FutureExt};
+use spark_market_sdk::SparkMarketContract;
+use std::{
+    sync::{
+        self,
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::{Mutex, Notify, RwLock};
+
+use crate::{config::Config, orderbook::OrderId};
+
+use super::{
+    batch_size, enqueue, isolate, split_timed_out, CancelOrderOperation, DeadLetterQueue,
+    DepositOperation, ErrorKind, GasPolicy, MarketOrderOperation, MatchOrdersOperation,
+    OpenOrderOperation, Operation, OperationMessage, OperationResult, OperationStatus,
+    QueuedOperation, ReplaceOrderOperation, RetryPolicy, Trader, TraderLease, TransactionTracker,
+    WithdrawOperation,
+};
+
+/// Operations of a single trader taken for submission
+pub struct Batch {
+    pub trader: usize,
+    pub lease: TraderLease,
+    pub operations: Vec<QueuedOperation>,
+}
+
+/// Operation parameters which can be swapped on config reload
+#[derive(Debug, Clone)]
+pub struct OperationParams {
+    pub multicall_size: usize,
+    /// Partial multicalls are submitted after this delay
+    pub batch_delay: Duration,
+    pub retry_policy: RetryPolicy,
+
+    /// Simulate every multicall before submission
+    pub dry_run: bool,
+}
+
+impl From<&Config> for OperationParams {
+    fn from(config: &Config) -> Self {
+        Self {
+            multicall_size: config.multicall_size,
+            batch_delay: Duration::from_millis(config.batch_delay),
+            retry_policy: RetryPolicy::from(&config.retry),
+            dry_run: config.dry_run,
+        }
+    }
+}
+
+pub struct OperationManager {
+    pub traders: Vec<Trader>,
+    /// Trader to start looking for the next batch from
+    pub next_trader: Mutex<usize>,
+    /// Notified when any trader finishes an in-flight transaction
+    pub released: Arc<Notify>,
+
+    pub params: sync::RwLock<OperationParams>,
+    /// No new orders are opened anymore, set on shutdown
+    pub is_closing: AtomicBool,
+    pub tracker: Arc<TransactionTracker>,
+    pub dead_letters: Arc<DeadLetterQueue>,
+    pub gas_policy: GasPolicy,
+}
+
+impl OperationManager {
+    pub fn new(
+        traders: Vec<Trader>,
+        params: OperationParams,
+        tracker: Arc<TransactionTracker>,
+        dead_letters: Arc<DeadLetterQueue>,
+        gas_policy: GasPolicy,
+    ) -> Self {
+        Self {
+            traders,
+            next_trader: Mutex::new(0),
+            released: Arc::new(Notify::new()),
+            params: sync::RwLock::new(params),
+            is_closing: AtomicBool::new(false),
+            tracker,
+            dead_letters,
+            gas_policy,
+        }
+    }
+
+    /// Current operation parameters
+    pub fn params(&self) -> OperationParams {
+        self.params.read().unwrap().clone()
+    }
+
+    /// Swap operation parameters, applied to the next batches
+    pub fn reload(&self, params: OperationParams) {
+        log::info!("Operation params reloaded: {:?}", params);
+        *self.params.write().unwrap() = params;
+    }
+
+    /// Index of the trader with the given address
+    pub fn trader_index(&self, address: &Address) -> Option<usize> {
+        self.traders
+            .iter()
+            .position(|trader| Address::from(trader.wallet.address()) == *address)
+    }
+
+    /// Add operation to the trader queue and return the length of that queue
+    pub async fn add(&self, message: &OperationMessage) -> usize {
+        let OperationMessage { operation, trader } = message;
+        let mut operation = match self.closing(operation) {
+            Some(operation) => operation,
+            None => return 0,
+        };
+
+        // A bad call can't be built, so it never gets into a batch
+        if let Err(e) = operation.validate() {
+            self.dead_letters.push(operation, 0, e).await;
+            return 0;
+        }
+
+        if let Some(order_id) = operation.cancelled_order() {
+            if self.is_cancelling(order_id).await {
+                log::debug!("Cancel of {} is already queued", order_id);
+
+                // The new quote of the replace is still needed
+                match operation {
+                    Operation::ReplaceOrder(ReplaceOrderOperation { order, .. }) => {
+                        operation = Operation::OpenOrder(order);
+                    }
+                    _ => return 0,
+                }
+            }
+        }
+
+        let trader = match trader.and_then(|address| self.trader_index(&address)) {
+            Some(trader) => trader,
+            None => self.assign_trader().await,
+        };
+
+        let mut operations = self.traders[trader].operations.lock().await;
+        enqueue(&mut operations, QueuedOperation::new(operation));
+
+        operations.len()
+    }
+
+    /// Check if the order is cancelled by a queued or in-flight operation
+    async fn is_cancelling(&self, order_id: &OrderId) -> bool {
+        let is_cancelled =
+            |queued: &QueuedOperation| queued.operation.cancelled_order() == Some(order_id);
+
+        for trader in &self.traders {
+            if trader.operations.lock().await.iter().any(is_cancelled) {
+                return true;
+            }
+        }
+
+        self.tracker
+            .pending
+            .lock()
+            .await
+            .values()
+            .any(|tx| tx.operations.iter().any(is_cancelled))
+    }
+
+    /// Check if there are no queued or in-flight operations
+    pub async fn is_idle(&self) -> bool {
+        for trader in &self.traders {
+            if trader.is_in_flight() || !trader.operations.lock().await.is_empty() {
+                return false;
+            }
+        }
+
+        self.tracker.pending.lock().await.is_empty()
+    }
+
+    /// Pick a trader for an operation which may be sent by anyone.
+    /// Prefer filling up batches of idle traders, otherwise the shortest queue.
+    async fn assign_trader(&self) -> usize {
+        let mut best = (0, (false, 0));
+
+        let multicall_size = self.params().multicall_size;
+
+        for (i, trader) in self.traders.iter().enumerate() {
+            let len = trader.operations.lock().await.len();
+            let is_idle = trader.in_flight.available_permits() > 0 && len < multicall_size;
+
+            // Idle traders with longer queues go first, busy ones with shorter queues
+            let score = if is_idle {
+                (true, len)
+            } else {
+                (false, usize::MAX - len)
+            };
+            if i == 0 || score > best.1 {
+                best = (i, score);
+            }
+        }
+
+        best.0
+    }
+
+    /// Put operations back to the trader queue, e.g. when their transaction was dropped
+    pub async fn requeue(&self, trader: usize, bunch: Vec<QueuedOperation>) -> usize {
+        let mut operations = self.traders[trader].operations.lock().await;
+        for mut queued in bunch {
+            match self.closing(&queued.operation) {
+                Some(operation) => queued.operation = operation,
+                None => continue,
+            }
+            enqueue(&mut operations, queued);
+        }
+
+        operations.len()
+    }
+
+    /// Stop opening new orders: queued opens are dropped and replaces only cancel.
+    /// The same applies to operations added or requeued later.
+    pub async fn close(&self) {
+        self.is_closing.store(true, Ordering::SeqCst);
+
+        for (i, trader) in self.traders.iter().enumerate() {
+            let bunch = std::mem::take(&mut *trader.operations.lock().await);
+            self.requeue(i, bunch).await;
+        }
+    }
+
+    /// Operation as it should be sent now, `None` if it only opens orders while closing
+    fn closing(&self, operation: &Operation) -> Option<Operation> {
+        if self.is_closing.load(Ordering::SeqCst) {
+            operation.closing()
+        } else {
+            Some(operation.clone())
+        }
+    }
+
+    /// Count a failed attempt for every operation of the bunch
+    /// and either schedule it again with backoff or move it to the dead-letter queue
+    pub async fn fail(
+        &self,
+        trader: usize,
+        bunch: Vec<QueuedOperation>,
+        error: String,
+        kind: ErrorKind,
+    ) -> usize {
+        let retry_policy = self.params().retry_policy;
+        let (retries, failed) = retry_policy.schedule(bunch, kind, Instant::now());
+
+        for queued in failed {
+            self.dead_letters
+                .push(queued.operation, queued.attempts, error.clone())
+                .await;
+        }
+
+        self.requeue(trader, retries).await
+    }
+
+    /// Handle the final status of a tracked transaction
+    pub async fn resolve(&self, result: OperationResult) -> usize {
+        let OperationResult {
+            trader,
+            operations,
+            status,
+            ..
+        } = result;
+
+        match status {
+            OperationStatus::Success => self.traders[trader].operations.lock().await.len(),
+            OperationStatus::Reverted(reason) if operations.len() == 1 => {
+                self.fail(trader, operations, reason, ErrorKind::Fatal)
+                    .await
+            }
+            OperationStatus::Reverted(reason) => {
+                // Any call may be the bad one, so each operation is sent alone
+                // without spending its attempts
+                log::warn!(
+                    "Retrying {} reverted operations one by one: {}",
+                    operations.len(),
+                    reason
+                );
+                self.requeue(trader, isolate(operations)).await
+            }
+            OperationStatus::Timeout => {
+                // The transaction may still land, so only operations
+                // which can't be executed twice are sent again
+                let (retries, unknown) = split_timed_out(operations);
+                for queued in unknown {
+                    self.dead_letters
+                        .push(
+                            queued.operation,
+                            queued.attempts + 1,
+                            "Transaction timed out, status unknown".into(),
+                        )
+                        .await;
+                }
+
+                self.fail(
+                    trader,
+                    retries,
+                    format!("{:?}", OperationStatus::Timeout),
+                    ErrorKind::Retryable,
+                )
+                .await
+            }
+            status => {
+                self.fail(
+                    trader,
+                    operations,
+                    format!("{:?}", status),
+                    ErrorKind::Retryable,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Time to submit partial multicalls: after the batch delay,
+    /// or earlier when a backoff of a queued operation ends
+    pub async fn next_flush(&self) -> Instant {
+        let now = Instant::now();
+        let mut flush_at = now + self.params().batch_delay;
+
+        for trader in &self.traders {
+            let operations = trader.operations.lock().await;
+            for queued in operations.iter().filter(|queued| !queued.is_ready(now)) {
+                flush_at = flush_at.min(queued.not_before);
+            }
+        }
+
+        flush_at
+    }
+
+    /// Take ready operations of the next trader with a free in-flight slot.
+    /// Waits while every trader with operations is busy, returns `None` if nothing is ready.
+    pub async fn next_batch(&self) -> Option<Batch> {
+        loop {
+            let released = self.released.notified();
+            let now = Instant::now();
+            let multicall_size = self.params().multicall_size;
+            let mut has_ready = false;
+
+            let mut next_trader = self.next_trader.lock().await;
+            for offset in 0..self.traders.len() {
+                let i = (*next_trader + offset) % self.traders.len();
+                let trader = &self.traders[i];
+
+                if trader.ready_len(now).await == 0 {
+                    continue;
+                }
+                has_ready = true;
+
+                let Some(lease) = trader.try_lease(self.released.clone()) else {
+                    continue;
+                };
+
+                // Take only operations which are not waiting for backoff.
+                // The queue is ordered by priority, so cancels go first
+                // and the rest of the multicall is filled with new orders.
+                let mut operations = trader.operations.lock().await;
+                let (mut bunch, rest): (Vec<_>, Vec<_>) = operations
+                    .drain(..)
+                    .partition(|queued| queued.is_ready(now));
+                let remaining = bunch.split_off(batch_size(&bunch, multicall_size));
+                *operations = remaining.into_iter().chain(rest).collect();
+
+                // Move turn to the next trader
+                *next_trader = (i + 1) % self.traders.len();
+
+                return Some(Batch {
+                    trader: i,
+                    lease,
+                    operations: bunch,
+                });
+            }
+            drop(next_trader);
+
+            if !has_ready {
+                return None;
+            }
+
+            // Backpressure: every trader with operations has its transactions in flight
+            released.await;
+        }
+    }
+
+    pub async fn process(&self, batch: Batch, market_contract: &Arc<RwLock<SparkMarketContract>>) {
+        let Batch {
+            trader,
+            lease,
+            operations,
+        } = batch;
+
+        if operations.is_empty() {
+            return;
+        }
+
+        self.submit(trader, lease, market_contract, operations)
+            .await;
+    }
+
+    /// Submit the bunch and return the lease back if it was not used by a transaction
+    fn submit<'a>(
+        &'a self,
+        trader: usize,
+        mut lease: TraderLease,
+        market_contract: &'a Arc<RwLock<SparkMarketContract>>,
+        bunch: Vec<QueuedOperation>,
+    ) -> BoxFuture<'a, Option<TraderLease>> {
+        async move {
+            let wallet = &self.traders[trader].wallet;
+            let multicall = self.build_multicall(wallet, market_contract, &bunch).await;
+
+            // Retried operations escalate the tip
+            let attempts = bunch
+                .iter()
+                .map(|queued| queued.attempts)
+                .max()
+                .unwrap_or(0);
+            let tip = self.gas_policy.tip.tip(attempts);
+
+            let calls = bunch.iter().map(|queued| queued.operation.calls()).sum();
+            let estimate = match self.gas_policy.estimate(&multicall, calls).await {
+                Ok(estimate) => estimate,
+                Err(e) => {
+                    log::warn!("Submitting despite failed estimation: {}", e);
+                    self.gas_policy.fallback(calls)
+                }
+            };
+            let multicall = multicall.with_tx_policies(
+                TxPolicies::default()
+                    .with_tip(tip)
+                    .with_script_gas_limit(estimate.gas_limit),
+            );
+
+            let tx = match self
+                .build_tx(trader, &mut lease, &multicall, estimate.fee + tip)
+                .await
+            {
+                Ok(tx) => tx,
+                Err(e) => {
+                    return self
+                        .handle_error(trader, lease, market_contract, bunch, e)
+                        .await
+                }
+            };
+
+            // Simulate the multicall to pay only for transactions that will succeed
+            if self.params().dry_run {
+                match self.simulate(&tx).await {
+                    Ok(None) => {}
+                    Ok(Some(reason)) if bunch.len() > 1 => {
+                        log::warn!("DRY RUN REVERTED: {}", reason);
+                        return self.bisect(trader, lease, market_contract, bunch).await;
+                    }
+                    Ok(Some(reason)) => {
+                        log::warn!("DRY RUN REVERTED: {}", reason);
+                        self.fail(trader, bunch, reason, ErrorKind::Fatal).await;
+                        return Some(lease);
+                    }
+                    Err(e) => {
+                        return self
+                            .handle_error(trader, lease, market_contract, bunch, e)
+                            .await
+                    }
+                }
+            }
+
+            // Send transactions without waiting for commit,
+            // the tracker checks the status of the transaction later
+            match self.tracker.provider.send_transaction(tx).await {
+                Ok(tx_id) => {
+                    log::info!("SUBMITTED: {:?}", tx_id);
+                    self.tracker.track(tx_id, trader, bunch, lease).await;
+                    None
+                }
+                Err(e) => {
+                    self.handle_error(trader, lease, market_contract, bunch, e)
+                        .await
+                }
+            }
+        }
+        .boxed()
+    }
+
+    async fn handle_error(
+        &self,
+        trader: usize,
+        lease: TraderLease,
+        market_contract: &Arc<RwLock<SparkMarketContract>>,
+        bunch: Vec<QueuedOperation>,
+        error: FuelError,
+    ) -> Option<TraderLease> {
+        log::error!("{:?}", error);
+        let kind = ErrorKind::classify(&error);
+
+        if kind == ErrorKind::Fatal && bunch.len() > 1 {
+            return self.bisect(trader, lease, market_contract, bunch).await;
+        }
+
+        self.fail(trader, bunch, error.to_string(), kind).await;
+        Some(lease)
+    }
+
+    /// Split the bunch in halves and submit them separately to isolate bad calls.
+    /// If the first half takes the in-flight slot, the second one waits in the queue.
+    async fn bisect(
+        &self,
+        trader: usize,
+        lease: TraderLease,
+        market_contract: &Arc<RwLock<SparkMarketContract>>,
+        bunch: Vec<QueuedOperation>,
+    ) -> Option<TraderLease> {
+        let mut left = bunch;
+        let right = left.split_off(left.len() / 2);
+
+        match self.submit(trader, lease, market_contract, left).await {
+            Some(lease) => self.submit(trader, lease, market_contract, right).await,
+            None => {
+                self.requeue(trader, right).await;
+                None
+            }
+        }
+    }
+
+    /// Build the transaction paying the fee with coins reserved for this lease
+    async fn build_tx(
+        &self,
+        trader: usize,
+        lease: &mut TraderLease,
+        multicall: &CallHandler<WalletUnlocked, Vec<ContractCall>, ()>,
+        fee: u64,
+    ) -> Result<ScriptTransaction, FuelError> {
+        let trader = &self.traders[trader];
+        let provider = &self.tracker.provider;
+
+        let coins = trader
+            .reserve_coins(lease, provider, *provider.base_asset_id(), fee)
+            .await?;
+
+        let mut tb = multicall.transaction_builder().await?;
+        tb.inputs_mut()
+            .extend(coins.into_iter().map(Input::resource_signed));
+
+        // Only the reserved coins pay the fee, topping up could take coins
+        // of other in-flight transactions. The rest goes back as change.
+        let base_asset_id = *provider.base_asset_id();
+        let has_change = tb.outputs().iter().any(|output| {
+            matches!(output, Output::Change { asset_id, .. } if *asset_id == base_asset_id)
+        });
+        if !has_change {
+            tb.outputs_mut().push(Output::change(
+                trader.wallet.address().into(),
+                0,
+                base_asset_id,
+            ));
+        }
+        trader.wallet.add_witnesses(&mut tb)?;
+
+        tb.build(provider).await
+    }
+
+    /// Dry-run the transaction and return the revert reason if it fails
+    async fn simulate(&self, tx: &ScriptTransaction) -> Result<Option<String>, FuelError> {
+        match self.tracker.provider.dry_run(tx.clone()).await? {
+            TxStatus::Revert { reason, .. } => Ok(Some(reason)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn build_multicall(
+        &self,
+        trader: &WalletUnlocked,
+        market_contract: &Arc<RwLock<SparkMarketContract>>,
+        bunch: &[QueuedOperation],
+    ) -> CallHandler<WalletUnlocked, Vec<ContractCall>, ()> {
+        let multicall = CallHandler::new_multi_call(trader.clone());
+        let market_contract = market_contract.read().await;
+        let contract = market_contract.with_account(trader);
+        let methods = contract.get_instance().methods();
+
+        let open_order = |order: OpenOrderOperation| {
+            methods.open_order(*order.amount, order.order_type.into(), order.price)
+        };
+        // Order ids are validated when operations are queued
+        let order_bits = |order_id: &OrderId| {
+            Bits256::from_hex_str(order_id).expect("Order id is validated in the queue")
+        };
+        let cancel_order = |order_id: OrderId| methods.cancel_order(order_bits(&order_id));
+        let order_ids = |orders: Vec<OrderId>| orders.iter().map(order_bits).collect::<Vec<_>>();
+
+        // Withdrawals send assets back to the trader
+        let withdrawals = bunch
+            .iter()
+            .filter(|queued| matches!(queued.operation, Operation::Withdraw(_)))
+            .count();
+        let multicall = if withdrawals > 0 {
+            multicall.with_variable_output_policy(VariableOutputPolicy::Exactly(withdrawals))
+        } else {
+            multicall
+        };
+
+        bunch.iter().fold(multicall, |multicall, queued| {
+            match queued.operation.to_owned() {
+                Operation::OpenOrder(order) => multicall.add_call(open_order(order)),
+                Operation::CancelOrder(CancelOrderOperation { order_id }) => {
+                    multicall.add_call(cancel_order(order_id))
+                }
+                // Both calls land in the same transaction, so the order is replaced atomically
+                Operation::ReplaceOrder(ReplaceOrderOperation { order_id, order }) => multicall
+                    .add_call(cancel_order(order_id))
+                    .add_call(open_order(order)),
+                Operation::MarketOrder(MarketOrderOperation {
+                    order_type,
+                    limit_type,
+                    amount,
+                    price,
+                    slippage,
+                    orders,
+                }) => multicall.add_call(methods.fulfill_order_many(
+                    *amount,
+                    order_type.into(),
+                    limit_type.into(),
+                    price,
+                    slippage,
+                    order_ids(orders),
+                )),
+                Operation::MatchOrders(MatchOrdersOperation { orders }) => {
+                    multicall.add_call(methods.match_order_many(order_ids(orders)))
+                }
+                Operation::Deposit(DepositOperation { asset, amount }) => {
+                    let call = methods
+                        .deposit()
+                        .call_params(
+                            CallParameters::default()
+                                .with_amount(*amount)
+                                .with_asset_id(asset.id),
+                        )
+                        .expect("Deposit is payable");
+
+                    multicall.add_call(call)
+                }
+                Operation::Withdraw(WithdrawOperation { asset_type, amount }) => {
+                    multicall.add_call(methods.withdraw(*amount, asset_type.into()))
+                }
+            }
+        })
+    }
+}
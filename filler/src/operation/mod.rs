@@ -0,0 +1,15 @@
+mod dead_letter;
+mod gas;
+mod manager;
+mod operation;
+mod retry;
+mod tracker;
+mod trader;
+
+pub use dead_letter::*;
+pub use gas::*;
+pub use manager::*;
+pub use operation::*;
+pub use retry::*;
+pub use tracker::*;
+pub use trader::*;
@@ -0,0 +1,374 @@
+use fuels::types::{Address, Bits256};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use crate::{
+    orderbook::{OrderId, OrderType},
+    types::{Amount, Asset},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Operation {
+    OpenOrder(OpenOrderOperation),
+    CancelOrder(CancelOrderOperation),
+    ReplaceOrder(ReplaceOrderOperation),
+    MarketOrder(MarketOrderOperation),
+    MatchOrders(MatchOrdersOperation),
+    Deposit(DepositOperation),
+    Withdraw(WithdrawOperation),
+}
+
+/// Priority class of the operation, higher goes to multicalls first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OperationPriority {
+    Open,
+    Transfer,
+    Replace,
+    Cancel,
+}
+
+impl Operation {
+    pub fn priority(&self) -> OperationPriority {
+        match self {
+            Operation::OpenOrder(_) => OperationPriority::Open,
+            Operation::CancelOrder(_) => OperationPriority::Cancel,
+            Operation::ReplaceOrder(_) => OperationPriority::Replace,
+            Operation::MarketOrder(_) | Operation::MatchOrders(_) => OperationPriority::Open,
+            // Balances should be ready before new orders are opened
+            Operation::Deposit(_) | Operation::Withdraw(_) => OperationPriority::Transfer,
+        }
+    }
+
+    /// Number of contract calls the operation takes in multicall
+    pub fn calls(&self) -> usize {
+        match self {
+            Operation::ReplaceOrder(_) => 2,
+            _ => 1,
+        }
+    }
+
+    /// Submitting the operation twice has the same effect as once,
+    /// the second cancel reverts as a whole if the first one was included
+    pub fn is_idempotent(&self) -> bool {
+        matches!(self, Operation::CancelOrder(_) | Operation::ReplaceOrder(_))
+    }
+
+    /// The part of the operation which doesn't leave new orders on the book
+    pub fn closing(&self) -> Option<Operation> {
+        match self {
+            Operation::OpenOrder(_) => None,
+            Operation::ReplaceOrder(ReplaceOrderOperation { order_id, .. }) => {
+                Some(Operation::CancelOrder(CancelOrderOperation {
+                    order_id: order_id.clone(),
+                }))
+            }
+            operation => Some(operation.clone()),
+        }
+    }
+
+    /// Check that the order ids of the operation can be encoded into contract calls
+    pub fn validate(&self) -> Result<(), String> {
+        let order_ids = match self {
+            Operation::CancelOrder(CancelOrderOperation { order_id })
+            | Operation::ReplaceOrder(ReplaceOrderOperation { order_id, .. }) => {
+                vec![order_id]
+            }
+            Operation::MarketOrder(MarketOrderOperation { orders, .. })
+            | Operation::MatchOrders(MatchOrdersOperation { orders }) => orders.iter().collect(),
+            _ => vec![],
+        };
+
+        for order_id in order_ids {
+            Bits256::from_hex_str(order_id)
+                .map_err(|e| format!("Invalid order id {}: {}", order_id, e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Order which is cancelled by the operation
+    pub fn cancelled_order(&self) -> Option<&OrderId> {
+        match self {
+            Operation::CancelOrder(CancelOrderOperation { order_id })
+            | Operation::ReplaceOrder(ReplaceOrderOperation { order_id, .. }) => Some(order_id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenOrderOperation {
+    pub order_type: OrderType,
+    pub base: Asset,
+    pub quote: Asset,
+    pub amount: Amount,
+    pub price: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelOrderOperation {
+    pub order_id: OrderId,
+}
+
+/// Execution rule of a market order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LimitType {
+    /// Fill as much as possible and cancel the rest
+    IOC,
+    /// Fill the whole amount or revert
+    FOK,
+}
+
+impl From<LimitType> for spark_market_sdk::LimitType {
+    fn from(val: LimitType) -> Self {
+        match val {
+            LimitType::IOC => spark_market_sdk::LimitType::IOC,
+            LimitType::FOK => spark_market_sdk::LimitType::FOK,
+        }
+    }
+}
+
+/// Market side of the asset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AssetType {
+    Base,
+    Quote,
+}
+
+impl From<AssetType> for spark_market_sdk::AssetType {
+    fn from(val: AssetType) -> Self {
+        match val {
+            AssetType::Base => spark_market_sdk::AssetType::Base,
+            AssetType::Quote => spark_market_sdk::AssetType::Quote,
+        }
+    }
+}
+
+/// Take resting orders immediately instead of opening a new one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketOrderOperation {
+    pub order_type: OrderType,
+    pub limit_type: LimitType,
+    pub amount: Amount,
+    /// Worst acceptable price
+    pub price: u64,
+    pub slippage: u64,
+    /// Resting orders to fill
+    pub orders: Vec<OrderId>,
+}
+
+/// Match crossed orders of other users
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchOrdersOperation {
+    pub orders: Vec<OrderId>,
+}
+
+/// Deposit trader's asset to the market balance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepositOperation {
+    pub asset: Asset,
+    pub amount: Amount,
+}
+
+/// Withdraw asset from the market balance back to the trader
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WithdrawOperation {
+    pub asset_type: AssetType,
+    pub amount: Amount,
+}
+
+/// Cancel the order and open its replacement in the same multicall
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceOrderOperation {
+    pub order_id: OrderId,
+    pub order: OpenOrderOperation,
+}
+
+#[derive(Debug, Clone)]
+pub struct OperationMessage {
+    pub operation: Operation,
+    /// Trader who must send the operation, e.g. the owner of the order to cancel
+    pub trader: Option<Address>,
+}
+
+/// Operation waiting in the queue with its retry state
+#[derive(Debug, Clone)]
+pub struct QueuedOperation {
+    pub operation: Operation,
+    /// Number of failed submissions
+    pub attempts: u32,
+    /// Operation is not submitted before this time
+    pub not_before: Instant,
+    /// Submitted in its own multicall, to find out which call reverted the last one
+    pub is_isolated: bool,
+}
+
+impl QueuedOperation {
+    pub fn new(operation: Operation) -> Self {
+        Self {
+            operation,
+            attempts: 0,
+            not_before: Instant::now(),
+            is_isolated: false,
+        }
+    }
+
+    pub fn is_ready(&self, now: Instant) -> bool {
+        self.not_before <= now
+    }
+}
+
+/// Insert the operation after all operations of the same or higher priority
+pub fn enqueue(operations: &mut Vec<QueuedOperation>, queued: QueuedOperation) {
+    let priority = queued.operation.priority();
+    let index = operations
+        .iter()
+        .position(|other| other.operation.priority() < priority)
+        .unwrap_or(operations.len());
+
+    operations.insert(index, queued);
+}
+
+/// Number of ready operations from the head of the queue which go into one multicall.
+/// Isolated operation at the head goes alone, otherwise at least one is taken
+/// even if it doesn't fit, e.g. a replace into a single call multicall.
+pub fn batch_size(operations: &[QueuedOperation], multicall_size: usize) -> usize {
+    match operations.first() {
+        Some(queued) if queued.is_isolated => 1,
+        Some(_) => {
+            let mut calls = 0;
+            operations
+                .iter()
+                .position(|queued| {
+                    calls += queued.operation.calls();
+                    calls > multicall_size || queued.is_isolated
+                })
+                .unwrap_or(operations.len())
+                .max(1)
+        }
+        None => 0,
+    }
+}
+
+/// Mark operations of a reverted multicall to be sent one by one,
+/// any call may be the bad one
+pub fn isolate(operations: Vec<QueuedOperation>) -> Vec<QueuedOperation> {
+    operations
+        .into_iter()
+        .map(|mut queued| {
+            queued.is_isolated = true;
+            queued
+        })
+        .collect()
+}
+
+/// Split operations of a timed out transaction into those which can be sent again
+/// and those which would be executed twice if the transaction still lands
+pub fn split_timed_out(
+    operations: Vec<QueuedOperation>,
+) -> (Vec<QueuedOperation>, Vec<QueuedOperation>) {
+    operations
+        .into_iter()
+        .partition(|queued| queued.operation.is_idempotent())
+}
+
+#[cfg(test)]
+mod tests {
+    use fuels::types::AssetId;
+
+    use super::*;
+
+    fn open() -> QueuedOperation {
+        QueuedOperation::new(Operation::OpenOrder(OpenOrderOperation {
+            order_type: OrderType::Buy,
+            base: Asset::new(AssetId::zeroed(), 9),
+            quote: Asset::new(AssetId::zeroed(), 6),
+            amount: Amount::new(1000),
+            price: 1000,
+        }))
+    }
+
+    fn cancel(order_id: &str) -> QueuedOperation {
+        QueuedOperation::new(Operation::CancelOrder(CancelOrderOperation {
+            order_id: order_id.into(),
+        }))
+    }
+
+    fn replace(order_id: &str) -> QueuedOperation {
+        let Operation::OpenOrder(order) = open().operation else {
+            unreachable!()
+        };
+        QueuedOperation::new(Operation::ReplaceOrder(ReplaceOrderOperation {
+            order_id: order_id.into(),
+            order,
+        }))
+    }
+
+    fn isolated(mut queued: QueuedOperation) -> QueuedOperation {
+        queued.is_isolated = true;
+        queued
+    }
+
+    #[test]
+    fn enqueue_by_priority() {
+        let mut operations = Vec::new();
+        enqueue(&mut operations, open());
+        enqueue(&mut operations, cancel("0x01"));
+        enqueue(&mut operations, replace("0x02"));
+        enqueue(&mut operations, cancel("0x03"));
+
+        let cancelled = operations
+            .iter()
+            .map(|queued| queued.operation.cancelled_order().map(String::as_str))
+            .collect::<Vec<_>>();
+        assert_eq!(cancelled, [Some("0x01"), Some("0x03"), Some("0x02"), None]);
+    }
+
+    #[test]
+    fn batch_size_by_calls() {
+        let operations = [cancel("0x01"), replace("0x02"), open(), open()];
+
+        assert_eq!(batch_size(&operations, 4), 3);
+        assert_eq!(batch_size(&operations, 10), 4);
+        assert_eq!(batch_size(&[], 4), 0);
+    }
+
+    #[test]
+    fn batch_size_takes_operation_over_size() {
+        assert_eq!(batch_size(&[replace("0x01"), open()], 1), 1);
+    }
+
+    #[test]
+    fn batch_size_sends_isolated_alone() {
+        let operations = [isolated(cancel("0x01")), cancel("0x02")];
+        assert_eq!(batch_size(&operations, 4), 1);
+
+        let operations = [cancel("0x01"), cancel("0x02"), isolated(cancel("0x03"))];
+        assert_eq!(batch_size(&operations, 4), 2);
+    }
+
+    #[test]
+    fn isolates_reverted_operations() {
+        let operations = isolate(vec![cancel("0x01"), open()]);
+
+        assert!(operations.iter().all(|queued| queued.is_isolated));
+        assert!(operations.iter().all(|queued| queued.attempts == 0));
+    }
+
+    #[test]
+    fn splits_timed_out_by_idempotence() {
+        let (retries, unknown) = split_timed_out(vec![open(), cancel("0x01"), replace("0x02")]);
+
+        assert_eq!(retries.len(), 2);
+        assert!(retries
+            .iter()
+            .all(|queued| queued.operation.is_idempotent()));
+        assert!(matches!(
+            unknown[..],
+            [QueuedOperation {
+                operation: Operation::OpenOrder(_),
+                ..
+            }]
+        ));
+    }
+}
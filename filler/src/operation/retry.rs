@@ -0,0 +1,168 @@
+use fuels::types::errors::{transaction::Reason, Error as FuelError};
+use std::{
+    cmp,
+    time::{Duration, Instant},
+};
+
+use crate::config::RetryConfig;
+
+use super::QueuedOperation;
+
+/// Messages of errors that won't disappear after resubmission.
+/// Coin selection failures ("not enough coins") are not among them, coins held by
+/// other in-flight transactions of the trader come back once those are resolved.
+const FATAL_PATTERNS: &[&str] = &[
+    "Revert",
+    "Panic",
+    "NotEnoughBalance",
+    "InsufficientBalance",
+    "InsufficientFeeAmount",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Network or node issue, the same call may succeed later
+    Retryable,
+    /// The call itself is invalid and fails every time
+    Fatal,
+}
+
+impl ErrorKind {
+    pub fn classify(error: &FuelError) -> Self {
+        match error {
+            FuelError::Transaction(Reason::Reverted { .. }) => ErrorKind::Fatal,
+            FuelError::Transaction(Reason::SqueezedOut(_)) => ErrorKind::Retryable,
+            error => {
+                let message = error.to_string();
+                if FATAL_PATTERNS.iter().any(|p| message.contains(p)) {
+                    ErrorKind::Fatal
+                } else {
+                    ErrorKind::Retryable
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts after which the operation goes to the dead-letter queue
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl From<&RetryConfig> for RetryPolicy {
+    fn from(config: &RetryConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts,
+            base_delay: Duration::from_millis(config.base_delay),
+            max_delay: Duration::from_millis(config.max_delay),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential delay before the next attempt
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        cmp::min(self.base_delay.saturating_mul(factor), self.max_delay)
+    }
+
+    pub fn is_exhausted(&self, attempts: u32) -> bool {
+        attempts >= self.max_attempts
+    }
+
+    /// Count a failed attempt for every operation and schedule the ones which can be retried.
+    /// Returns the retries and the operations to move to the dead-letter queue.
+    pub fn schedule(
+        &self,
+        bunch: Vec<QueuedOperation>,
+        kind: ErrorKind,
+        now: Instant,
+    ) -> (Vec<QueuedOperation>, Vec<QueuedOperation>) {
+        let mut retries = Vec::new();
+        let mut failed = Vec::new();
+
+        for mut queued in bunch {
+            queued.attempts += 1;
+
+            if kind == ErrorKind::Fatal || self.is_exhausted(queued.attempts) {
+                failed.push(queued);
+                continue;
+            }
+
+            queued.not_before = now + self.backoff(queued.attempts);
+            retries.push(queued);
+        }
+
+        (retries, failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operation::{CancelOrderOperation, Operation};
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_millis(1500),
+        }
+    }
+
+    fn queued(attempts: u32) -> QueuedOperation {
+        let mut queued = QueuedOperation::new(Operation::CancelOrder(CancelOrderOperation {
+            order_id: "0x01".into(),
+        }));
+        queued.attempts = attempts;
+        queued
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let policy = policy();
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(3), Duration::from_millis(1500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn is_exhausted_at_max_attempts() {
+        let policy = policy();
+
+        assert!(!policy.is_exhausted(2));
+        assert!(policy.is_exhausted(3));
+    }
+
+    #[test]
+    fn schedule_retries_with_backoff() {
+        let now = Instant::now();
+        let (retries, failed) =
+            policy().schedule(vec![queued(0), queued(1)], ErrorKind::Retryable, now);
+
+        assert!(failed.is_empty());
+        assert_eq!(retries[0].attempts, 1);
+        assert_eq!(retries[0].not_before, now + Duration::from_millis(500));
+        assert_eq!(retries[1].attempts, 2);
+        assert_eq!(retries[1].not_before, now + Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn schedule_fails_fatal_and_exhausted() {
+        let now = Instant::now();
+
+        let (retries, failed) = policy().schedule(vec![queued(0)], ErrorKind::Fatal, now);
+        assert!(retries.is_empty());
+        assert_eq!(failed[0].attempts, 1);
+
+        let (retries, failed) =
+            policy().schedule(vec![queued(1), queued(2)], ErrorKind::Retryable, now);
+        assert_eq!(retries.len(), 1);
+        assert_eq!(failed[0].attempts, 3);
+    }
+}
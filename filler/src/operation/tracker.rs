@@ -0,0 +1,149 @@
+use fuels::{
+    accounts::provider::Provider,
+    types::{tx_status::TxStatus, TxId},
+};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::{broadcast, Mutex};
+
+use super::{QueuedOperation, TraderLease};
+
+/// Final status of the submitted operations
+#[derive(Debug, Clone)]
+pub enum OperationStatus {
+    /// Transaction was included in a block
+    Success,
+    /// Transaction was included, but reverted with the reason
+    Reverted(String),
+    /// Transaction was dropped by the node
+    SqueezedOut(String),
+    /// Transaction wasn't resolved in time, but may still be included
+    Timeout,
+}
+
+#[derive(Debug, Clone)]
+pub struct OperationResult {
+    pub tx_id: TxId,
+    /// Index of the trader who sent the transaction
+    pub trader: usize,
+    pub operations: Vec<QueuedOperation>,
+    pub status: OperationStatus,
+}
+
+pub struct PendingTransaction {
+    pub trader: usize,
+    pub operations: Vec<QueuedOperation>,
+    pub submitted_at: Instant,
+
+    /// Keeps the trader slot and coins reserved until the transaction is resolved
+    pub _lease: TraderLease,
+}
+
+/// Tracks the lifecycle of the submitted multicalls
+pub struct TransactionTracker {
+    pub provider: Provider,
+    pub pending: Arc<Mutex<HashMap<TxId, PendingTransaction>>>,
+    pub results_tx: broadcast::Sender<OperationResult>,
+
+    /// Time after which a pending transaction is considered lost
+    pub timeout: Duration,
+}
+
+impl TransactionTracker {
+    pub fn new(provider: Provider, timeout: Duration) -> Self {
+        let (results_tx, _) = broadcast::channel(1024);
+
+        Self {
+            provider,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            results_tx,
+            timeout,
+        }
+    }
+
+    /// Start tracking the submitted transaction
+    pub async fn track(
+        &self,
+        tx_id: TxId,
+        trader: usize,
+        operations: Vec<QueuedOperation>,
+        lease: TraderLease,
+    ) {
+        let mut pending = self.pending.lock().await;
+        pending.insert(
+            tx_id,
+            PendingTransaction {
+                trader,
+                operations,
+                submitted_at: Instant::now(),
+                _lease: lease,
+            },
+        );
+    }
+
+    /// Subscribe to the results of the tracked transactions
+    pub fn subscribe(&self) -> broadcast::Receiver<OperationResult> {
+        self.results_tx.subscribe()
+    }
+
+    /// Check statuses of all pending transactions and return resolved ones
+    pub async fn poll(&self) -> Vec<OperationResult> {
+        let tx_ids = self
+            .pending
+            .lock()
+            .await
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut resolved = Vec::new();
+        for tx_id in tx_ids {
+            let status = match self.provider.tx_status(&tx_id).await {
+                Ok(TxStatus::Success { .. }) => Some(OperationStatus::Success),
+                Ok(TxStatus::Revert { reason, .. }) => Some(OperationStatus::Reverted(reason)),
+                Ok(TxStatus::SqueezedOut { reason }) => Some(OperationStatus::SqueezedOut(reason)),
+                Ok(TxStatus::Submitted) => None,
+                Err(e) => {
+                    log::debug!("Error while getting status of {:?}: {}", tx_id, e);
+                    None
+                }
+            };
+
+            let mut pending = self.pending.lock().await;
+            let status = match status {
+                Some(status) => status,
+                None => match pending.get(&tx_id) {
+                    Some(tx) if tx.submitted_at.elapsed() > self.timeout => {
+                        OperationStatus::Timeout
+                    }
+                    _ => continue,
+                },
+            };
+
+            // Dropping the lease frees the trader for the next transaction
+            if let Some(tx) = pending.remove(&tx_id) {
+                resolved.push(OperationResult {
+                    tx_id,
+                    trader: tx.trader,
+                    operations: tx.operations,
+                    status,
+                });
+            }
+        }
+
+        for result in &resolved {
+            match &result.status {
+                OperationStatus::Success => log::info!("OK: {:?}", result.tx_id),
+                status => log::warn!("FAILED: {:?} {:?}", result.tx_id, status),
+            }
+
+            // No receivers is fine, strategies subscribe on demand
+            let _ = self.results_tx.send(result.clone());
+        }
+
+        resolved
+    }
+}
@@ -0,0 +1,144 @@
+use fuels::{
+    accounts::{
+        provider::{Provider, ResourceFilter},
+        wallet::WalletUnlocked,
+    },
+    tx::UtxoId,
+    types::{coin_type::CoinType, errors::Error as FuelError, AssetId},
+};
+use std::{
+    collections::HashSet,
+    sync::{self, Arc},
+    time::Instant,
+};
+use tokio::sync::{Mutex, Notify, OwnedSemaphorePermit, Semaphore};
+
+use super::QueuedOperation;
+
+/// Trader wallet with its own operation queue
+pub struct Trader {
+    pub wallet: WalletUnlocked,
+    pub operations: Mutex<Vec<QueuedOperation>>,
+
+    /// Slots for transactions of the trader which are not resolved yet
+    pub in_flight: Arc<Semaphore>,
+
+    pub max_in_flight: usize,
+
+    /// Coins used by in-flight transactions of the trader
+    pub reserved_coins: Arc<sync::Mutex<HashSet<UtxoId>>>,
+
+    /// Serializes coin selection, so concurrent transactions never pick the same coin
+    selection: Mutex<()>,
+}
+
+/// In-flight slot of the trader, released together with its coins on drop
+pub struct TraderLease {
+    _permit: OwnedSemaphorePermit,
+    coins: Vec<UtxoId>,
+    reserved_coins: Arc<sync::Mutex<HashSet<UtxoId>>>,
+    released: Arc<Notify>,
+}
+
+impl TraderLease {
+    fn reserve(&mut self, coins: Vec<UtxoId>) {
+        let mut reserved_coins = self.reserved_coins.lock().unwrap();
+        reserved_coins.extend(coins.iter().cloned());
+        self.coins.extend(coins);
+    }
+
+    fn release_coins(&mut self) {
+        let mut reserved_coins = self.reserved_coins.lock().unwrap();
+        for coin in self.coins.drain(..) {
+            reserved_coins.remove(&coin);
+        }
+    }
+}
+
+impl Drop for TraderLease {
+    fn drop(&mut self) {
+        self.release_coins();
+        self.released.notify_one();
+    }
+}
+
+impl Trader {
+    pub fn new(wallet: WalletUnlocked, max_in_flight: usize) -> Self {
+        Self {
+            wallet,
+            operations: Mutex::new(Vec::new()),
+            in_flight: Arc::new(Semaphore::new(max_in_flight)),
+            max_in_flight,
+            reserved_coins: Arc::new(sync::Mutex::new(HashSet::new())),
+            selection: Mutex::new(()),
+        }
+    }
+
+    /// Take an in-flight slot if the trader is not busy
+    pub fn try_lease(&self, released: Arc<Notify>) -> Option<TraderLease> {
+        let permit = self.in_flight.clone().try_acquire_owned().ok()?;
+
+        Some(TraderLease {
+            _permit: permit,
+            coins: Vec::new(),
+            reserved_coins: self.reserved_coins.clone(),
+            released,
+        })
+    }
+
+    /// Whether the trader has batches being submitted or transactions not resolved yet
+    pub fn is_in_flight(&self) -> bool {
+        self.in_flight.available_permits() < self.max_in_flight
+    }
+
+    /// Number of queued operations ready to be submitted
+    pub async fn ready_len(&self, now: Instant) -> usize {
+        let operations = self.operations.lock().await;
+        operations
+            .iter()
+            .filter(|queued| queued.is_ready(now))
+            .count()
+    }
+
+    /// Select coins to pay the fee, skipping coins of other in-flight transactions
+    pub async fn reserve_coins(
+        &self,
+        lease: &mut TraderLease,
+        provider: &Provider,
+        asset_id: AssetId,
+        amount: u64,
+    ) -> Result<Vec<CoinType>, FuelError> {
+        let _selection = self.selection.lock().await;
+        lease.release_coins();
+
+        let excluded_utxos = self
+            .reserved_coins
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let coins = provider
+            .get_spendable_resources(ResourceFilter {
+                from: self.wallet.address().clone(),
+                asset_id: Some(asset_id),
+                amount,
+                excluded_utxos,
+                excluded_message_nonces: vec![],
+            })
+            .await?;
+
+        lease.reserve(
+            coins
+                .iter()
+                .filter_map(|coin| match coin {
+                    CoinType::Coin(coin) => Some(coin.utxo_id),
+                    _ => None,
+                })
+                .collect(),
+        );
+
+        Ok(coins)
+    }
+}
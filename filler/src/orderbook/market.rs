@@ -0,0 +1,64 @@
+use fuels::types::{Address, Bits256, Identity};
+use spark_market_sdk::SparkMarketContract;
+
+use super::{OrderId, OrderType};
+use crate::error::Error;
+
+/// Open order as stored in the market contract
+#[derive(Debug, Clone)]
+pub struct OpenOrder {
+    pub id: OrderId,
+    pub order_type: OrderType,
+    pub amount: u64,
+    pub price: u64,
+}
+
+/// Hex order id as used by the indexer
+pub fn order_id(id: &Bits256) -> OrderId {
+    let id =
+        id.0.iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+    format!("0x{}", id)
+}
+
+/// Open orders of the users read from the market contract,
+/// unlike the subscribed book they are not limited to the best prices
+pub async fn user_orders(
+    market_contract: &SparkMarketContract,
+    users: impl IntoIterator<Item = Address>,
+) -> Result<Vec<(Address, OrderId)>, Error> {
+    let mut orders = Vec::new();
+
+    for user in users {
+        let order_ids = market_contract
+            .user_orders(Identity::Address(user))
+            .await
+            .map_err(|e| Error::Market(e.to_string()))?
+            .value;
+
+        orders.extend(order_ids.iter().map(|id| (user, order_id(id))));
+    }
+
+    Ok(orders)
+}
+
+/// Get the open order from the market contract, `None` if it's closed
+pub async fn open_order(
+    market_contract: &SparkMarketContract,
+    id: &OrderId,
+) -> Result<Option<OpenOrder>, Error> {
+    let bits = Bits256::from_hex_str(id).map_err(|e| Error::Market(e.to_string()))?;
+    let order = market_contract
+        .order(bits)
+        .await
+        .map_err(|e| Error::Market(e.to_string()))?
+        .value;
+
+    Ok(order.map(|order| OpenOrder {
+        id: id.clone(),
+        order_type: order.order_type.into(),
+        amount: order.amount,
+        price: order.price,
+    }))
+}
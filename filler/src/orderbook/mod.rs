@@ -1,9 +1,11 @@
 mod envio;
+mod market;
 mod order;
 mod orderbook;
 mod subscriber;
 
 pub use envio::*;
+pub use market::*;
 pub use order::*;
 pub use orderbook::*;
 pub use subscriber::*;
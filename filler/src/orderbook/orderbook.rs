@@ -1,4 +1,9 @@
-use std::collections::BTreeMap;
+use fuels::types::Address;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashSet},
+    str::FromStr,
+};
 
 use super::{Order, OrderType};
 
@@ -42,8 +47,146 @@ impl Orderbook {
         self.sell.values().next()
     }
 
+    /// Middle between the best bid and the best ask
+    pub fn mid_price(&self) -> Option<u64> {
+        let bid = self.best_bid()?.price;
+        let ask = self.best_ask()?.price;
+        Some(((bid + ask) / 2) as u64)
+    }
+
+    /// Orders placed by the given users together with the owner address
+    pub fn orders_by_users(&self, users: &HashSet<Address>) -> Vec<(Address, &Order)> {
+        self.buy
+            .values()
+            .chain(self.sell.values())
+            .filter_map(|order| {
+                let user = Address::from_str(&order.user).ok()?;
+                users.contains(&user).then_some((user, order))
+            })
+            .collect()
+    }
+
+    /// Pairs of buy and sell orders which can be matched with each other, best prices first.
+    /// Partially matched order stays in the next pair with the rest of its amount.
+    pub fn crossed_pairs(&self) -> Vec<(&Order, &Order)> {
+        let mut pairs = Vec::new();
+        let mut buys = self.buy.values().rev().peekable();
+        let mut sells = self.sell.values().peekable();
+        let (mut buy_left, mut sell_left) = (None, None);
+
+        while let (Some(&buy), Some(&sell)) = (buys.peek(), sells.peek()) {
+            if buy.price < sell.price {
+                break;
+            }
+            pairs.push((buy, sell));
+
+            let buy_amount = buy_left.unwrap_or(buy.amount);
+            let sell_amount = sell_left.unwrap_or(sell.amount);
+            match buy_amount.cmp(&sell_amount) {
+                Ordering::Less => {
+                    buy_left = None;
+                    sell_left = Some(sell_amount - buy_amount);
+                    buys.next();
+                }
+                Ordering::Greater => {
+                    buy_left = Some(buy_amount - sell_amount);
+                    sell_left = None;
+                    sells.next();
+                }
+                Ordering::Equal => {
+                    buy_left = None;
+                    sell_left = None;
+                    buys.next();
+                    sells.next();
+                }
+            }
+        }
+
+        pairs
+    }
+
     pub fn clear(&mut self) {
         self.buy.clear();
         self.sell.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(id: &str, order_type: OrderType, amount: u128, price: u128) -> Order {
+        Order {
+            id: id.into(),
+            user: String::new(),
+            asset: String::new(),
+            order_type,
+            amount,
+            price,
+            timestamp: 0,
+        }
+    }
+
+    fn ids<'a>(pairs: &[(&'a Order, &'a Order)]) -> Vec<(&'a str, &'a str)> {
+        pairs
+            .iter()
+            .map(|(buy, sell)| (buy.id.as_str(), sell.id.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn crossed_pairs_empty_when_not_crossed() {
+        let mut orderbook = Orderbook::new();
+        orderbook.insert(order("b1", OrderType::Buy, 10, 99));
+        orderbook.insert(order("s1", OrderType::Sell, 10, 100));
+
+        assert!(orderbook.crossed_pairs().is_empty());
+    }
+
+    #[test]
+    fn crossed_pairs_match_equal_prices() {
+        let mut orderbook = Orderbook::new();
+        orderbook.insert(order("b1", OrderType::Buy, 10, 100));
+        orderbook.insert(order("s1", OrderType::Sell, 10, 100));
+
+        assert_eq!(ids(&orderbook.crossed_pairs()), vec![("b1", "s1")]);
+    }
+
+    #[test]
+    fn crossed_pairs_best_prices_first() {
+        let mut orderbook = Orderbook::new();
+        orderbook.insert(order("b1", OrderType::Buy, 10, 105));
+        orderbook.insert(order("b2", OrderType::Buy, 10, 103));
+        orderbook.insert(order("s1", OrderType::Sell, 10, 101));
+        orderbook.insert(order("s2", OrderType::Sell, 10, 104));
+
+        // The second best buy is below the second best sell
+        assert_eq!(ids(&orderbook.crossed_pairs()), vec![("b1", "s1")]);
+    }
+
+    #[test]
+    fn crossed_pairs_keep_partially_matched_order() {
+        let mut orderbook = Orderbook::new();
+        orderbook.insert(order("b1", OrderType::Buy, 25, 105));
+        orderbook.insert(order("s1", OrderType::Sell, 10, 100));
+        orderbook.insert(order("s2", OrderType::Sell, 10, 101));
+        orderbook.insert(order("s3", OrderType::Sell, 10, 102));
+
+        // The buy of 25 fills two sells and a half of the third one
+        assert_eq!(
+            ids(&orderbook.crossed_pairs()),
+            vec![("b1", "s1"), ("b1", "s2"), ("b1", "s3")]
+        );
+    }
+
+    #[test]
+    fn crossed_pairs_stop_when_leftover_no_longer_crosses() {
+        let mut orderbook = Orderbook::new();
+        orderbook.insert(order("b1", OrderType::Buy, 5, 105));
+        orderbook.insert(order("b2", OrderType::Buy, 5, 100));
+        orderbook.insert(order("s1", OrderType::Sell, 20, 102));
+
+        // The rest of the sell is above the second buy
+        assert_eq!(ids(&orderbook.crossed_pairs()), vec![("b1", "s1")]);
+    }
+}
@@ -4,6 +4,7 @@ use serde_json::json;
 use std::sync::Arc;
 use tokio::{net::TcpStream, sync::RwLock};
 use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tokio_util::sync::CancellationToken;
 
 use super::{OrderType, Orderbook};
 use crate::{config::Config, error::Error, orderbook::OrderResponseEnvio};
@@ -21,16 +22,27 @@ impl OrderbookSubscriber {
         OrderbookSubscriber { ws_host }
     }
 
-    pub async fn start(&self, orderbook: Arc<RwLock<Orderbook>>) -> Result<(), Error> {
-        loop {
+    pub async fn start(
+        &self,
+        orderbook: Arc<RwLock<Orderbook>>,
+        shutdown: CancellationToken,
+    ) -> Result<(), Error> {
+        while !shutdown.is_cancelled() {
             log::info!("Connecting to indexer...");
-            let (ws_stream, _) = match connect_async(self.ws_host.to_string()).await {
+            let connection = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                connection = connect_async(self.ws_host.to_string()) => connection,
+            };
+            let (ws_stream, _) = match connection {
                 Ok(res) => res,
                 Err(e) => {
                     log::error!("Error while connecting to indexer: {}", e);
 
                     // Reconnecting delay
-                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                    tokio::select! {
+                        _ = shutdown.cancelled() => break,
+                        _ = tokio::time::sleep(tokio::time::Duration::from_secs(5)) => {}
+                    }
 
                     continue;
                 }
@@ -43,7 +55,15 @@ impl OrderbookSubscriber {
             sink.send(Message::Text(r#"{"type": "connection_init"}"#.into()))
                 .await?;
 
-            while let Some(message) = ws_stream.next().await {
+            loop {
+                let message = tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    message = ws_stream.next() => message,
+                };
+                let Some(message) = message else {
+                    break;
+                };
+
                 match message {
                     Ok(Message::Text(text)) => {
                         if let Ok(res) = serde_json::from_str::<OrderResponseEnvio>(&text) {
@@ -89,13 +109,14 @@ impl OrderbookSubscriber {
                 }
             }
 
-            // TODO: Rewrite to shutdown gracefully
             self.unsubscribe(&mut sink, OrderType::Buy).await?;
             self.unsubscribe(&mut sink, OrderType::Sell).await?;
 
             log::info!("Closing connection with indexer...");
-            sink.close().await.unwrap();
+            sink.close().await?;
         }
+
+        Ok(())
     }
 
     pub async fn subscribe(&self, sink: &mut Sink, order_type: OrderType) -> Result<(), Error> {
@@ -0,0 +1,243 @@
+use std::{env, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+
+use crate::{
+    config::{Config, PriceProviderConfig},
+    error::Error,
+};
+
+use super::{
+    pair_key, BinanceApi, CoingeckoApi, FixedPriceApi, KrakenApi, PriceApi, PriceSnapshot, PythApi,
+    PythOracleApi,
+};
+
+/// Queries several providers concurrently and returns the median price without outliers
+pub struct AggregatedPriceApi {
+    pub providers: Vec<Arc<dyn PriceApi>>,
+    /// Maximum relative distance from the median before a provider price is discarded
+    pub max_deviation: f64,
+    /// Minimum number of agreeing providers to accept the price
+    pub min_sources: usize,
+}
+
+#[async_trait]
+impl PriceApi for AggregatedPriceApi {
+    fn name(&self) -> &'static str {
+        "aggregated"
+    }
+
+    /// Get the prices of the given coin ids in USD
+    async fn prices(&self, ids: &[&str]) -> Result<Vec<Option<f64>>, Error> {
+        let snapshots = self.snapshots(ids).await?;
+        Ok(snapshots
+            .into_iter()
+            .map(|snapshot| snapshot.map(|snapshot| snapshot.value))
+            .collect())
+    }
+
+    /// Get the price of the pair from providers which support it directly,
+    /// `None` if not enough of them do
+    async fn pair_snapshot(
+        &self,
+        base: &str,
+        quote: &str,
+    ) -> Result<Option<PriceSnapshot<f64>>, Error> {
+        let pair = pair_key(base, quote);
+        let results = join_all(
+            self.providers
+                .iter()
+                .map(|provider| provider.pair_price(base, quote)),
+        )
+        .await;
+
+        let mut quotes = Vec::new();
+        for (provider, result) in self.providers.iter().zip(results) {
+            match result {
+                Ok(Some(price)) if price.is_finite() && price > 0.0 => {
+                    quotes.push((provider.name(), price))
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("Error while getting {} {}: {}", provider.name(), pair, e),
+            }
+        }
+
+        if quotes.len() < self.min_sources {
+            return Ok(None);
+        }
+
+        match self.aggregate(&pair, quotes) {
+            Ok(price) => Ok(Some(price)),
+            Err(e) => {
+                log::warn!("{}, using USD prices", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Get the prices of the given coin ids in USD with the share of agreeing providers,
+    /// an id without enough agreeing providers is left unpriced instead of failing the others
+    async fn snapshots(&self, ids: &[&str]) -> Result<Vec<Option<PriceSnapshot<f64>>>, Error> {
+        let results = join_all(self.providers.iter().map(|provider| provider.prices(ids))).await;
+
+        // Collect prices of every id from the providers which responded
+        let mut quotes = vec![Vec::new(); ids.len()];
+        let mut responded = 0;
+        for (provider, result) in self.providers.iter().zip(results) {
+            match result {
+                Ok(prices) => {
+                    if prices.iter().any(Option::is_some) {
+                        responded += 1;
+                    }
+                    for (i, price) in prices.into_iter().enumerate().take(ids.len()) {
+                        match price {
+                            Some(price) if price.is_finite() && price > 0.0 => {
+                                quotes[i].push((provider.name(), price))
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Err(e) => log::warn!("Error while getting {} prices: {}", provider.name(), e),
+            }
+        }
+
+        if responded == 0 {
+            return Err(Error::PriceApi("No prices from any provider".into()));
+        }
+
+        Ok(ids
+            .iter()
+            .zip(quotes)
+            .map(|(id, quotes)| match self.aggregate(id, quotes) {
+                Ok(price) => Some(price),
+                Err(e) => {
+                    log::warn!("{}, {} is not priced", e, id);
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+impl AggregatedPriceApi {
+    pub fn new(providers: Vec<Arc<dyn PriceApi>>, max_deviation: f64, min_sources: usize) -> Self {
+        Self {
+            providers,
+            max_deviation,
+            min_sources,
+        }
+    }
+
+    /// Create providers from the price config
+    pub fn from_config(config: &Config) -> Result<Self, Error> {
+        let providers = config
+            .price
+            .providers
+            .iter()
+            .map(|provider| -> Result<Arc<dyn PriceApi>, Error> {
+                Ok(match provider.clone() {
+                    PriceProviderConfig::Coingecko {
+                        plan,
+                        timeout,
+                        vs_currencies,
+                    } => {
+                        let api_key = env::var("COINGECKO_API_KEY")
+                            .map_err(|_| Error::PriceApi("COINGECKO_API_KEY is not set".into()))?;
+                        Arc::new(CoingeckoApi::new(
+                            plan,
+                            api_key,
+                            Duration::from_millis(timeout),
+                            vs_currencies,
+                        ))
+                    }
+                    PriceProviderConfig::Binance {
+                        host,
+                        symbols,
+                        timeout,
+                    } => Arc::new(BinanceApi::new(
+                        host,
+                        symbols,
+                        Duration::from_millis(timeout),
+                    )),
+                    PriceProviderConfig::Pyth {
+                        host,
+                        feeds,
+                        timeout,
+                    } => Arc::new(PythApi::new(host, feeds, Duration::from_millis(timeout))),
+                    PriceProviderConfig::PythOracle {
+                        node_url,
+                        contract_id,
+                        feeds,
+                        max_age,
+                    } => Arc::new(PythOracleApi::new(node_url, contract_id, feeds, max_age)),
+                    PriceProviderConfig::Kraken {
+                        host,
+                        pairs,
+                        timeout,
+                    } => Arc::new(KrakenApi::new(host, pairs, Duration::from_millis(timeout))),
+                    PriceProviderConfig::Fixed { prices } => Arc::new(FixedPriceApi::new(prices)),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self::new(
+            providers,
+            config.price.max_deviation,
+            config.price.min_sources,
+        ))
+    }
+
+    /// Median of the provider prices, discarding the ones too far from it
+    fn aggregate(&self, id: &str, quotes: Vec<(&str, f64)>) -> Result<PriceSnapshot<f64>, Error> {
+        let median_price = median(quotes.iter().map(|(_, price)| *price).collect())
+            .ok_or_else(|| Error::PriceApi(format!("No prices for {}", id)))?;
+
+        let (accepted, outliers): (Vec<_>, Vec<_>) = quotes.into_iter().partition(|(_, price)| {
+            (price - median_price).abs() / median_price <= self.max_deviation
+        });
+        for (name, price) in outliers {
+            log::warn!(
+                "OUTLIER: {} {} {} (median {})",
+                name,
+                id,
+                price,
+                median_price
+            );
+        }
+
+        if accepted.len() < self.min_sources {
+            return Err(Error::PriceApi(format!(
+                "Only {} of {} sources agree on {}",
+                accepted.len(),
+                self.min_sources,
+                id
+            )));
+        }
+
+        let sources = accepted.len();
+        let confidence = sources as f64 / self.providers.len() as f64;
+        let price = median(accepted.into_iter().map(|(_, price)| price).collect())
+            .ok_or_else(|| Error::PriceApi(format!("No prices for {}", id)))?;
+
+        Ok(PriceSnapshot {
+            sources,
+            ..PriceSnapshot::new(price, self.name(), confidence)
+        })
+    }
+}
+
+pub fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        Some((values[mid - 1] + values[mid]) / 2.0)
+    } else {
+        Some(values[mid])
+    }
+}
@@ -1,9 +1,86 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
+use reqwest::RequestBuilder;
+use serde::de::DeserializeOwned;
 
 use crate::error::Error;
 
+use super::PriceSnapshot;
+
 #[async_trait]
 pub trait PriceApi: Send + Sync {
-    /// Get the prices of the given coin ids in USD
-    async fn prices(&self, ids: &[&str]) -> Result<Vec<f64>, Error>;
+    /// Provider name for logs
+    fn name(&self) -> &'static str;
+
+    /// Get the prices of the given coin ids in USD, `None` for the ids the provider
+    /// couldn't price, so one bad id doesn't discard the prices of the others
+    async fn prices(&self, ids: &[&str]) -> Result<Vec<Option<f64>>, Error>;
+
+    /// Get the price of the base coin id in terms of the quote coin id,
+    /// `None` if the provider can't price the pair directly
+    async fn pair_price(&self, _base: &str, _quote: &str) -> Result<Option<f64>, Error> {
+        Ok(None)
+    }
+
+    /// Get the price of the pair directly with its origin, `None` if it's not supported
+    async fn pair_snapshot(
+        &self,
+        base: &str,
+        quote: &str,
+    ) -> Result<Option<PriceSnapshot<f64>>, Error> {
+        let price = self.pair_price(base, quote).await?;
+        Ok(price.map(|price| PriceSnapshot::new(price, self.name(), 1.0)))
+    }
+
+    /// Get the price of the base coin id in terms of the quote coin id with its origin.
+    /// Falls back to dividing USD prices when the pair can't be priced directly.
+    async fn snapshot(&self, base: &str, quote: &str) -> Result<PriceSnapshot<f64>, Error> {
+        if let Some(price) = self.pair_snapshot(base, quote).await? {
+            return Ok(price);
+        }
+
+        match self.snapshots(&[base, quote]).await?[..] {
+            [Some(base_price), Some(quote_price)] => Ok(base_price.cross(&quote_price)),
+            _ => Err(Error::PriceApi(format!("No prices for {}/{}", base, quote))),
+        }
+    }
+
+    /// Get the prices of the given coin ids in USD with their origin,
+    /// `None` for the ids which couldn't be priced
+    async fn snapshots(&self, ids: &[&str]) -> Result<Vec<Option<PriceSnapshot<f64>>>, Error> {
+        let prices = self.prices(ids).await?;
+
+        Ok(prices
+            .into_iter()
+            .map(|price| price.map(|price| PriceSnapshot::new(price, self.name(), 1.0)))
+            .collect())
+    }
+}
+
+/// Key of the directly priced pair in provider mappings (ethereum/bitcoin)
+pub fn pair_key(base: &str, quote: &str) -> String {
+    format!("{}/{}", base, quote)
+}
+
+/// Send the request with the timeout and parse the JSON response,
+/// failed requests are reported with the provider name
+pub async fn get_json<R: DeserializeOwned>(
+    provider: &str,
+    request: RequestBuilder,
+    timeout: Duration,
+) -> Result<R, Error> {
+    let res = request.timeout(timeout).send().await?;
+
+    // Error responses have a different body, report the status instead
+    let status = res.status();
+    if !status.is_success() {
+        let body = res.text().await.unwrap_or_default();
+        return Err(Error::PriceApi(format!(
+            "{}: {} {}",
+            provider, status, body
+        )));
+    }
+
+    Ok(res.json().await?)
 }
@@ -0,0 +1,208 @@
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use crate::{error::Error, types::Sender};
+
+use super::{get_json, pair_key, PriceApi, PriceSnapshot, PriceStream, PriceUpdate};
+
+/// Binance spot REST API, USDT pairs are treated as USD
+pub struct BinanceApi {
+    pub host: String,
+    /// Price ids mapped to Binance symbols (bitcoin -> BTCUSDT),
+    /// pairs mapped to native symbols (ethereum/bitcoin -> ETHBTC)
+    pub symbols: HashMap<String, String>,
+    /// Request timeout
+    pub timeout: Duration,
+    pub client: Client,
+}
+
+#[async_trait]
+impl PriceApi for BinanceApi {
+    fn name(&self) -> &'static str {
+        "binance"
+    }
+
+    /// Get the prices of the given coin ids in USD
+    async fn prices(&self, ids: &[&str]) -> Result<Vec<Option<f64>>, Error> {
+        // Ids without a symbol are left unpriced
+        let symbols = ids
+            .iter()
+            .map(|&id| self.symbols.get(id))
+            .collect::<Vec<_>>();
+        let requested = symbols.iter().flatten().collect::<Vec<_>>();
+        if requested.is_empty() {
+            return Ok(vec![None; ids.len()]);
+        }
+
+        let tickers = self.ticker_price(&requested).await?;
+        Ok(symbols
+            .iter()
+            .map(|symbol| {
+                let symbol = symbol?;
+                tickers
+                    .iter()
+                    .find(|ticker| ticker.symbol == *symbol)
+                    .and_then(|ticker| ticker.price.parse().ok())
+            })
+            .collect())
+    }
+
+    /// Get the price of the pair from its native or inverse symbol
+    async fn pair_price(&self, base: &str, quote: &str) -> Result<Option<f64>, Error> {
+        let (symbol, is_inverse) = match (
+            self.symbols.get(&pair_key(base, quote)),
+            self.symbols.get(&pair_key(quote, base)),
+        ) {
+            (Some(symbol), _) => (symbol, false),
+            (None, Some(symbol)) => (symbol, true),
+            (None, None) => return Ok(None),
+        };
+
+        let tickers = self.ticker_price(&[symbol]).await?;
+        let price = tickers
+            .first()
+            .and_then(|ticker| ticker.price.parse::<f64>().ok())
+            .ok_or_else(|| Error::PriceApi(format!("Binance: no price for {}", symbol)))?;
+
+        Ok(Some(if is_inverse { 1.0 / price } else { price }))
+    }
+}
+
+impl BinanceApi {
+    pub fn new(host: String, symbols: HashMap<String, String>, timeout: Duration) -> Self {
+        Self {
+            host,
+            symbols,
+            timeout,
+            client: Client::new(),
+        }
+    }
+
+    async fn get<R: DeserializeOwned>(&self, endpoint: &str) -> Result<R, Error> {
+        let request = self.client.get(&format!("{}{}", self.host, endpoint));
+        get_json("Binance", request, self.timeout).await
+    }
+
+    /// Get the latest prices of the given symbols
+    pub async fn ticker_price<S: AsRef<str>>(&self, symbols: &[S]) -> Result<Vec<Ticker>, Error> {
+        let symbols = symbols
+            .iter()
+            .map(|symbol| format!("%22{}%22", symbol.as_ref()))
+            .collect::<Vec<_>>();
+
+        let req = format!("/api/v3/ticker/price?symbols=%5B{}%5D", symbols.join(","));
+
+        self.get(&req).await
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Ticker {
+    pub symbol: String,
+    pub price: String,
+}
+
+/// Binance websocket book ticker streams, mid price of USDT pairs is treated as USD
+pub struct BinanceStream {
+    pub host: String,
+    /// Price ids mapped to Binance symbols (bitcoin -> BTCUSDT),
+    /// pairs mapped to native symbols (ethereum/bitcoin -> ETHBTC)
+    pub symbols: HashMap<String, String>,
+}
+
+#[async_trait]
+impl PriceStream for BinanceStream {
+    fn name(&self) -> &'static str {
+        "binance"
+    }
+
+    /// Stream the prices of the given ids until the connection is closed,
+    /// ids without a symbol are skipped
+    async fn stream(&self, ids: &[&str], updates: Sender<PriceUpdate>) -> Result<(), Error> {
+        let symbols = ids
+            .iter()
+            .map(|&id| self.symbols.get(id).map(|symbol| symbol.to_lowercase()))
+            .collect::<Vec<_>>();
+
+        let streams = symbols
+            .iter()
+            .flatten()
+            .map(|symbol| format!("{}@bookTicker", symbol))
+            .collect::<Vec<_>>();
+        if streams.is_empty() {
+            return Err(Error::PriceApi(format!(
+                "Binance: no symbols for {:?}",
+                ids
+            )));
+        }
+
+        log::info!("Connecting to binance stream...");
+        let (mut ws_stream, _) = connect_async(format!(
+            "{}/stream?streams={}",
+            self.host,
+            streams.join("/")
+        ))
+        .await?;
+
+        while let Some(message) = ws_stream.next().await {
+            let Message::Text(text) = message? else {
+                continue;
+            };
+            let Ok(message) = serde_json::from_str::<StreamMessage<BookTicker>>(&text) else {
+                continue;
+            };
+
+            let ticker = message.data;
+            let index = symbols.iter().position(|symbol| {
+                symbol
+                    .as_ref()
+                    .is_some_and(|symbol| symbol.eq_ignore_ascii_case(&ticker.symbol))
+            });
+            if let (Some(index), Some(price)) = (index, ticker.mid()) {
+                let price = PriceSnapshot::new(price, self.name(), 1.0);
+                if updates.send(PriceUpdate { index, price }).is_err() {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl BinanceStream {
+    pub fn new(host: String, symbols: HashMap<String, String>) -> Self {
+        Self { host, symbols }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StreamMessage<T> {
+    pub stream: String,
+    pub data: T,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BookTicker {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    /// Best bid price
+    #[serde(rename = "b")]
+    pub bid: String,
+    /// Best ask price
+    #[serde(rename = "a")]
+    pub ask: String,
+}
+
+impl BookTicker {
+    pub fn mid(&self) -> Option<f64> {
+        let bid = self.bid.parse::<f64>().ok()?;
+        let ask = self.ask.parse::<f64>().ok()?;
+        Some((bid + ask) / 2.0)
+    }
+}
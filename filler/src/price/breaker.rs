@@ -0,0 +1,264 @@
+use crate::config::BreakerConfig;
+
+use super::PriceSnapshot;
+
+/// Rejects external prices which jump too far, until the move is confirmed
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    /// Maximum relative jump from the last accepted price
+    pub max_jump: f64,
+    /// Maximum relative distance from the on-chain mid price
+    pub max_mid_deviation: f64,
+    /// Number of agreeing providers of a later price which confirms a tripped move
+    pub min_confirmations: usize,
+
+    /// Last price passed through the breaker
+    pub last_accepted: Option<PriceSnapshot>,
+    /// Rejected price waiting for confirmation
+    pub tripped: Option<PriceSnapshot>,
+    /// On-chain mid which the accepted price is far from, it doesn't trip again
+    /// until the mid moves
+    pub accepted_mid: Option<u64>,
+    /// Accept the next price without checks
+    acknowledged: bool,
+}
+
+impl From<&BreakerConfig> for CircuitBreaker {
+    fn from(config: &BreakerConfig) -> Self {
+        Self {
+            max_jump: config.max_jump,
+            max_mid_deviation: config.max_mid_deviation,
+            min_confirmations: config.min_confirmations,
+            last_accepted: None,
+            tripped: None,
+            accepted_mid: None,
+            acknowledged: false,
+        }
+    }
+}
+
+impl CircuitBreaker {
+    pub fn is_tripped(&self) -> bool {
+        self.tripped.is_some()
+    }
+
+    /// Manually accept the move, the next price passes without checks
+    pub fn acknowledge(&mut self) {
+        self.acknowledged = true;
+    }
+
+    /// Check the new price against the last accepted one and the on-chain mid.
+    /// A tripped move is accepted once a later price agrees with it and comes from
+    /// a different source or enough agreeing providers.
+    pub fn check(&mut self, price: &PriceSnapshot, mid: Option<u64>) -> bool {
+        if self.acknowledged {
+            log::warn!("CIRCUIT BREAKER: price {} acknowledged", price.value);
+            return self.accept(price, mid);
+        }
+
+        if let Some(tripped) = &self.tripped {
+            let is_confirmed = tripped.source != price.source
+                || (price.fetched_at > tripped.fetched_at
+                    && price.sources >= self.min_confirmations);
+            if is_confirmed && deviation(price.value, tripped.value) <= self.max_jump {
+                log::warn!(
+                    "CIRCUIT BREAKER: move to {} confirmed by {} ({} sources)",
+                    price.value,
+                    price.source,
+                    price.sources
+                );
+                return self.accept(price, mid);
+            }
+        }
+
+        let reason = if self
+            .last_accepted
+            .is_some_and(|last| deviation(price.value, last.value) > self.max_jump)
+        {
+            Some("last price")
+        } else if mid
+            .is_some_and(|mid| self.is_off_mid(price.value, mid) && !self.is_accepted_mid(mid))
+        {
+            Some("on-chain mid")
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => {
+                if self.tripped.is_none() {
+                    log::error!(
+                        "CIRCUIT BREAKER: price {} from {} is too far from the {}, strategies paused",
+                        price.value,
+                        price.source,
+                        reason
+                    );
+                }
+                self.tripped = Some(*price);
+                false
+            }
+            None => self.accept(price, mid),
+        }
+    }
+
+    fn accept(&mut self, price: &PriceSnapshot, mid: Option<u64>) -> bool {
+        // The mid is known to be off once a price far from it is confirmed
+        self.accepted_mid = mid.filter(|&mid| self.is_off_mid(price.value, mid));
+        self.last_accepted = Some(*price);
+        self.tripped = None;
+        self.acknowledged = false;
+        true
+    }
+
+    fn is_off_mid(&self, price: u64, mid: u64) -> bool {
+        deviation(price, mid) > self.max_mid_deviation
+    }
+
+    /// The mid didn't move since a price far from it was accepted
+    fn is_accepted_mid(&self, mid: u64) -> bool {
+        self.accepted_mid
+            .is_some_and(|accepted_mid| deviation(mid, accepted_mid) <= self.max_jump)
+    }
+}
+
+fn deviation(price: u64, reference: u64) -> f64 {
+    (price as f64 - reference as f64).abs() / reference as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::from(&BreakerConfig {
+            max_jump: 0.05,
+            max_mid_deviation: 0.1,
+            min_confirmations: 2,
+        })
+    }
+
+    /// Price fetched `at` seconds after the start with `sources` agreeing providers
+    fn price(
+        start: Instant,
+        at: u64,
+        value: u64,
+        source: &'static str,
+        sources: usize,
+    ) -> PriceSnapshot {
+        PriceSnapshot {
+            value,
+            source,
+            fetched_at: start + Duration::from_secs(at),
+            confidence: 1.0,
+            sources,
+        }
+    }
+
+    #[test]
+    fn check_accepts_small_moves() {
+        let start = Instant::now();
+        let mut breaker = breaker();
+
+        assert!(breaker.check(&price(start, 0, 1000, "aggregated", 2), Some(1000)));
+        assert!(breaker.check(&price(start, 1, 1040, "aggregated", 2), Some(1000)));
+        assert!(!breaker.is_tripped());
+    }
+
+    #[test]
+    fn check_trips_on_jump_from_last_price() {
+        let start = Instant::now();
+        let mut breaker = breaker();
+
+        assert!(breaker.check(&price(start, 0, 1000, "aggregated", 2), None));
+        assert!(!breaker.check(&price(start, 1, 1100, "aggregated", 2), None));
+        assert!(breaker.is_tripped());
+        assert_eq!(breaker.last_accepted.map(|price| price.value), Some(1000));
+    }
+
+    #[test]
+    fn check_trips_on_deviation_from_mid() {
+        let start = Instant::now();
+        let mut breaker = breaker();
+
+        assert!(!breaker.check(&price(start, 0, 1200, "aggregated", 2), Some(1000)));
+        assert!(breaker.is_tripped());
+    }
+
+    #[test]
+    fn check_confirms_move_by_agreeing_providers() {
+        let start = Instant::now();
+        let mut breaker = breaker();
+        breaker.check(&price(start, 0, 1000, "aggregated", 2), None);
+
+        assert!(!breaker.check(&price(start, 1, 1100, "aggregated", 2), None));
+        assert!(breaker.check(&price(start, 2, 1105, "aggregated", 2), None));
+        assert!(!breaker.is_tripped());
+        assert_eq!(breaker.last_accepted.map(|price| price.value), Some(1105));
+    }
+
+    #[test]
+    fn check_keeps_tripped_without_enough_providers() {
+        let start = Instant::now();
+        let mut breaker = breaker();
+        breaker.check(&price(start, 0, 1000, "aggregated", 2), None);
+
+        assert!(!breaker.check(&price(start, 1, 1100, "aggregated", 1), None));
+        assert!(!breaker.check(&price(start, 2, 1100, "aggregated", 1), None));
+        assert!(breaker.is_tripped());
+    }
+
+    #[test]
+    fn check_keeps_tripped_when_confirmation_disagrees() {
+        let start = Instant::now();
+        let mut breaker = breaker();
+        breaker.check(&price(start, 0, 1000, "aggregated", 2), None);
+
+        assert!(!breaker.check(&price(start, 1, 1100, "aggregated", 2), None));
+        assert!(!breaker.check(&price(start, 2, 1300, "aggregated", 3), None));
+        assert!(breaker.is_tripped());
+    }
+
+    #[test]
+    fn check_confirms_move_by_different_source() {
+        let start = Instant::now();
+        let mut breaker = breaker();
+        breaker.check(&price(start, 0, 1000, "aggregated", 2), None);
+
+        assert!(!breaker.check(&price(start, 1, 1100, "binance", 1), None));
+        assert!(breaker.check(&price(start, 1, 1100, "aggregated", 1), None));
+    }
+
+    #[test]
+    fn check_does_not_trip_again_on_the_same_far_mid() {
+        let start = Instant::now();
+        let mut breaker = breaker();
+
+        assert!(!breaker.check(&price(start, 0, 1000, "aggregated", 2), Some(800)));
+        assert!(breaker.check(&price(start, 1, 1005, "aggregated", 2), Some(800)));
+        assert_eq!(breaker.accepted_mid, Some(800));
+
+        // The book stays where it was
+        assert!(breaker.check(&price(start, 2, 1010, "aggregated", 2), Some(800)));
+        assert!(breaker.check(&price(start, 3, 1010, "binance", 1), Some(810)));
+        assert!(!breaker.is_tripped());
+
+        // The book catches up and falls behind again
+        assert!(breaker.check(&price(start, 4, 1010, "aggregated", 2), Some(1000)));
+        assert_eq!(breaker.accepted_mid, None);
+        assert!(!breaker.check(&price(start, 5, 1010, "aggregated", 2), Some(800)));
+    }
+
+    #[test]
+    fn check_accepts_acknowledged_move() {
+        let start = Instant::now();
+        let mut breaker = breaker();
+        breaker.check(&price(start, 0, 1000, "aggregated", 2), None);
+        assert!(!breaker.check(&price(start, 1, 1500, "aggregated", 1), None));
+
+        breaker.acknowledge();
+        assert!(breaker.check(&price(start, 2, 1500, "aggregated", 1), None));
+        assert!(!breaker.is_tripped());
+    }
+}
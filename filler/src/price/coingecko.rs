@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use async_trait::async_trait;
 use reqwest::{
@@ -9,31 +9,79 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::error::Error;
 
-use super::PriceApi;
+use super::{get_json, PriceApi};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoingeckoPlan {
+    Demo,
+    Pro,
+}
+
+impl CoingeckoPlan {
+    pub fn host(&self) -> &'static str {
+        match self {
+            CoingeckoPlan::Demo => "https://api.coingecko.com/api/v3",
+            CoingeckoPlan::Pro => "https://pro-api.coingecko.com/api/v3",
+        }
+    }
+
+    pub fn api_key_header(&self) -> &'static str {
+        match self {
+            CoingeckoPlan::Demo => "x-cg-demo-api-key",
+            CoingeckoPlan::Pro => "x-cg-pro-api-key",
+        }
+    }
+}
 
 pub struct CoingeckoApi {
-    pub host: String,
+    pub plan: CoingeckoPlan,
     pub api_key: String,
+    /// Request timeout
+    pub timeout: Duration,
+    /// Price ids mapped to Coingecko vs currencies (bitcoin -> btc)
+    pub vs_currencies: HashMap<String, String>,
     pub client: Client,
 }
 
 #[async_trait]
 impl PriceApi for CoingeckoApi {
+    fn name(&self) -> &'static str {
+        "coingecko"
+    }
+
     /// Get the prices of the given coin ids in USD
-    async fn prices(&self, ids: &[&str]) -> Result<Vec<f64>, Error> {
+    async fn prices(&self, ids: &[&str]) -> Result<Vec<Option<f64>>, Error> {
         let price_map = self.simple_price(ids, &["usd"]).await?;
         Ok(ids
             .iter()
-            .map(|&id| price_map[id].usd.unwrap())
-            .collect::<Vec<_>>())
+            .map(|&id| price_map.get(id).and_then(|price| price.usd))
+            .collect())
+    }
+
+    /// Get the price of the base coin id in the vs currency of the quote coin id
+    async fn pair_price(&self, base: &str, quote: &str) -> Result<Option<f64>, Error> {
+        let Some(vs_currency) = self.vs_currencies.get(quote) else {
+            return Ok(None);
+        };
+
+        let price_map = self.simple_price(&[base], &[vs_currency]).await?;
+        Ok(price_map.get(base).and_then(|price| price.get(vs_currency)))
     }
 }
 
 impl CoingeckoApi {
-    pub fn new(host: String, api_key: String) -> Self {
+    pub fn new(
+        plan: CoingeckoPlan,
+        api_key: String,
+        timeout: Duration,
+        vs_currencies: HashMap<String, String>,
+    ) -> Self {
         Self {
-            host,
+            plan,
             api_key,
+            timeout,
+            vs_currencies,
             client: Client::new(),
         }
     }
@@ -42,21 +90,18 @@ impl CoingeckoApi {
     async fn get<R: DeserializeOwned>(&self, endpoint: &str) -> Result<R, Error> {
         let mut headers = HeaderMap::new();
         headers.insert(
-            // TODO: Replace demo name
-            "x-cg-demo-api-key",
+            self.plan.api_key_header(),
             HeaderValue::from_str(&self.api_key).map_err(|e| Error::PriceApi(e.to_string()))?,
         );
 
-        let res = self
+        let request = self
             .client
-            .get(&format!("{}{}", self.host, endpoint))
-            .headers(headers)
-            .send()
-            .await?
-            .json()
-            .await?;
-
-        Ok(res)
+            .get(&format!("{}{}", self.plan.host(), endpoint))
+            .headers(headers);
+
+        // Rate limits depend on the plan, report it with the errors
+        let provider = format!("Coingecko {:?} plan", self.plan);
+        get_json(&provider, request, self.timeout).await
     }
 
     /// Get the simple price of the given coin ids in the given vs currencies
@@ -83,4 +128,18 @@ pub struct Price {
     pub btc: Option<f64>,
     pub eth: Option<f64>,
     pub usd: Option<f64>,
+    /// Prices in the rest of vs currencies
+    #[serde(flatten)]
+    pub other: HashMap<String, f64>,
+}
+
+impl Price {
+    pub fn get(&self, vs_currency: &str) -> Option<f64> {
+        match vs_currency {
+            "btc" => self.btc,
+            "eth" => self.eth,
+            "usd" => self.usd,
+            _ => self.other.get(vs_currency).copied(),
+        }
+    }
 }
@@ -0,0 +1,30 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::error::Error;
+
+use super::PriceApi;
+
+/// Static prices, useful for testing and as a last resort reference
+pub struct FixedPriceApi {
+    pub prices: HashMap<String, f64>,
+}
+
+#[async_trait]
+impl PriceApi for FixedPriceApi {
+    fn name(&self) -> &'static str {
+        "fixed"
+    }
+
+    /// Get the prices of the given coin ids in USD
+    async fn prices(&self, ids: &[&str]) -> Result<Vec<Option<f64>>, Error> {
+        Ok(ids.iter().map(|&id| self.prices.get(id).copied()).collect())
+    }
+}
+
+impl FixedPriceApi {
+    pub fn new(prices: HashMap<String, f64>) -> Self {
+        Self { prices }
+    }
+}
@@ -0,0 +1,212 @@
+use std::{collections::VecDeque, time::Duration};
+
+use super::PriceSnapshot;
+
+/// Ring buffer of the latest accepted prices of a market, oldest first.
+/// Volatilities are per square root of second, so they don't depend on the update rate.
+#[derive(Debug, Clone)]
+pub struct PriceHistory {
+    pub capacity: usize,
+    pub snapshots: VecDeque<PriceSnapshot>,
+}
+
+impl PriceHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            snapshots: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Add the price, dropping the oldest one when full
+    pub fn push(&mut self, snapshot: PriceSnapshot) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    pub fn latest(&self) -> Option<&PriceSnapshot> {
+        self.snapshots.back()
+    }
+
+    /// Prices of the window with the one in effect at its start
+    fn window(&self, window: Duration) -> impl Iterator<Item = &PriceSnapshot> {
+        let start = self
+            .snapshots
+            .iter()
+            .rposition(|snapshot| snapshot.age() >= window)
+            .unwrap_or(0);
+        self.snapshots.iter().skip(start)
+    }
+
+    /// Log returns with the elapsed seconds between consecutive prices
+    fn returns<'a>(snapshots: impl Iterator<Item = &'a PriceSnapshot>) -> Vec<(f64, f64)> {
+        let snapshots = snapshots.collect::<Vec<_>>();
+        snapshots
+            .windows(2)
+            .filter(|pair| pair[0].value > 0 && pair[1].value > 0)
+            .map(|pair| {
+                let ret = (pair[1].value as f64 / pair[0].value as f64).ln();
+                let elapsed = (pair[1].fetched_at - pair[0].fetched_at).as_secs_f64();
+                (ret, elapsed)
+            })
+            .filter(|(_, elapsed)| *elapsed > 0.0)
+            .collect()
+    }
+
+    /// Exponentially weighted volatility, `lambda` is the decay of the previous variance
+    /// (0.94 in RiskMetrics)
+    pub fn ewma_volatility(&self, lambda: f64) -> Option<f64> {
+        let variance = Self::returns(self.snapshots.iter()).into_iter().fold(
+            None,
+            |variance, (ret, elapsed)| {
+                let sample = ret * ret / elapsed;
+                Some(match variance {
+                    Some(variance) => lambda * variance + (1.0 - lambda) * sample,
+                    None => sample,
+                })
+            },
+        );
+
+        variance.map(f64::sqrt)
+    }
+
+    /// Volatility from the sum of squared returns over the window
+    pub fn realized_volatility(&self, window: Duration) -> Option<f64> {
+        let (sum, elapsed) = Self::returns(self.window(window))
+            .into_iter()
+            .fold((0.0, 0.0), |(sum, total), (ret, elapsed)| {
+                (sum + ret * ret, total + elapsed)
+            });
+
+        (elapsed > 0.0).then(|| (sum / elapsed).sqrt())
+    }
+
+    /// Time weighted average price over the window, the latest price lasts until now
+    pub fn twap(&self, window: Duration) -> Option<u64> {
+        let snapshots = self.window(window).collect::<Vec<_>>();
+        let mut weighted = 0.0;
+        let mut total = 0.0;
+
+        for (i, snapshot) in snapshots.iter().enumerate() {
+            // The price in effect before the window only counts from the window start
+            let start = snapshot.age().min(window);
+            let end = snapshots
+                .get(i + 1)
+                .map_or(Duration::ZERO, |next| next.age().min(window));
+            let weight = start.saturating_sub(end).as_secs_f64();
+
+            weighted += snapshot.value as f64 * weight;
+            total += weight;
+        }
+
+        match snapshots.last() {
+            _ if total > 0.0 => Some((weighted / total).round() as u64),
+            // All prices are too recent to weight
+            Some(latest) => Some(latest.value),
+            None => None,
+        }
+    }
+
+    /// Log return from the price at the start of the window to the latest one
+    pub fn momentum(&self, window: Duration) -> Option<f64> {
+        let first = self.window(window).next()?;
+        let latest = self.latest()?;
+        if first.value == 0 || latest.value == 0 {
+            return None;
+        }
+
+        Some((latest.value as f64 / first.value as f64).ln())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    /// History of `(seconds ago, price)` pairs, oldest first
+    fn history(prices: &[(u64, u64)]) -> PriceHistory {
+        let now = Instant::now();
+        let mut history = PriceHistory::new(prices.len());
+        for &(ago, value) in prices {
+            history.push(PriceSnapshot {
+                fetched_at: now - Duration::from_secs(ago),
+                ..PriceSnapshot::new(value, "fixed", 1.0)
+            });
+        }
+        history
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("no value");
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn push_drops_oldest_when_full() {
+        let mut history = history(&[(20, 100), (10, 110)]);
+        history.push(PriceSnapshot::new(120, "fixed", 1.0));
+
+        let values = history.snapshots.iter().map(|price| price.value);
+        assert_eq!(values.collect::<Vec<_>>(), vec![110, 120]);
+        assert_eq!(history.latest().map(|price| price.value), Some(120));
+    }
+
+    #[test]
+    fn twap_weights_prices_by_time_in_window() {
+        // 100 lasts for the first half of the window, 200 until now
+        let history = history(&[(30, 100), (10, 200)]);
+
+        assert_eq!(history.twap(Duration::from_secs(20)), Some(150));
+    }
+
+    #[test]
+    fn twap_of_recent_price_is_the_price() {
+        let history = history(&[(0, 100)]);
+
+        assert_eq!(history.twap(Duration::from_secs(20)), Some(100));
+        assert_eq!(PriceHistory::new(10).twap(Duration::from_secs(20)), None);
+    }
+
+    #[test]
+    fn realized_volatility_of_window() {
+        let history = history(&[(20, 100), (10, 110), (0, 100)]);
+        let ret = 1.1f64.ln();
+
+        assert_close(
+            history.realized_volatility(Duration::from_secs(30)),
+            (2.0 * ret * ret / 20.0).sqrt(),
+        );
+        assert_eq!(history.realized_volatility(Duration::ZERO), None);
+    }
+
+    #[test]
+    fn ewma_volatility_of_equal_samples() {
+        let single = history(&[(0, 100)]);
+        let history = history(&[(20, 100), (10, 110), (0, 100)]);
+
+        // Both returns have the same squared size, so any decay gives the same variance
+        assert_close(history.ewma_volatility(0.94), 1.1f64.ln() / 10f64.sqrt());
+        assert_eq!(single.ewma_volatility(0.94), None);
+    }
+
+    #[test]
+    fn momentum_from_price_at_window_start() {
+        let history = history(&[(30, 100), (10, 120), (0, 150)]);
+
+        assert_close(history.momentum(Duration::from_secs(20)), 1.5f64.ln());
+        assert_close(history.momentum(Duration::from_secs(5)), 1.25f64.ln());
+        assert_eq!(PriceHistory::new(10).momentum(Duration::from_secs(5)), None);
+    }
+}
@@ -0,0 +1,102 @@
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::error::Error;
+
+use super::{get_json, pair_key, PriceApi};
+
+/// Kraken public REST API
+pub struct KrakenApi {
+    pub host: String,
+    /// Price ids mapped to Kraken USD pairs (bitcoin -> XBTUSD),
+    /// pairs mapped to native pairs (ethereum/bitcoin -> ETHXBT)
+    pub pairs: HashMap<String, String>,
+    /// Request timeout
+    pub timeout: Duration,
+    pub client: Client,
+}
+
+#[async_trait]
+impl PriceApi for KrakenApi {
+    fn name(&self) -> &'static str {
+        "kraken"
+    }
+
+    /// Get the prices of the given coin ids in USD
+    async fn prices(&self, ids: &[&str]) -> Result<Vec<Option<f64>>, Error> {
+        // Kraken renames pairs in the response (XBTUSD -> XXBTZUSD), so request them one by one
+        Ok(join_all(ids.iter().map(|&id| async move {
+            let pair = self.pairs.get(id)?;
+
+            match self.last_price(pair).await {
+                Ok(price) => Some(price),
+                Err(e) => {
+                    log::warn!("Error while getting {} {}: {}", self.name(), id, e);
+                    None
+                }
+            }
+        }))
+        .await)
+    }
+
+    /// Get the price of the pair from its native or inverse pair
+    async fn pair_price(&self, base: &str, quote: &str) -> Result<Option<f64>, Error> {
+        if let Some(pair) = self.pairs.get(&pair_key(base, quote)) {
+            return Ok(Some(self.last_price(pair).await?));
+        }
+        if let Some(pair) = self.pairs.get(&pair_key(quote, base)) {
+            return Ok(Some(1.0 / self.last_price(pair).await?));
+        }
+
+        Ok(None)
+    }
+}
+
+impl KrakenApi {
+    pub fn new(host: String, pairs: HashMap<String, String>, timeout: Duration) -> Self {
+        Self {
+            host,
+            pairs,
+            timeout,
+            client: Client::new(),
+        }
+    }
+
+    async fn get<R: DeserializeOwned>(&self, endpoint: &str) -> Result<R, Error> {
+        let request = self.client.get(&format!("{}{}", self.host, endpoint));
+        let res: KrakenResponse<R> = get_json("Kraken", request, self.timeout).await?;
+        match res.result {
+            Some(result) if res.error.is_empty() => Ok(result),
+            _ => Err(Error::PriceApi(format!("Kraken: {}", res.error.join(", ")))),
+        }
+    }
+
+    /// Get the last trade price of the pair
+    pub async fn last_price(&self, pair: &str) -> Result<f64, Error> {
+        let tickers: HashMap<String, Ticker> =
+            self.get(&format!("/0/public/Ticker?pair={}", pair)).await?;
+
+        tickers
+            .values()
+            .next()
+            .and_then(|ticker| ticker.c.first())
+            .and_then(|price| price.parse().ok())
+            .ok_or_else(|| Error::PriceApi(format!("Kraken: no price for {}", pair)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KrakenResponse<R> {
+    pub error: Vec<String>,
+    pub result: Option<R>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Ticker {
+    /// Last trade closed: [price, lot volume]
+    pub c: Vec<String>,
+}
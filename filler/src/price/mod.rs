@@ -1,5 +1,27 @@
+mod aggregated;
 mod api;
+mod binance;
+mod breaker;
 mod coingecko;
+mod fixed;
+mod history;
+mod kraken;
+mod oracle;
+mod pyth;
+mod service;
+mod snapshot;
+mod stream;
 
+pub use aggregated::*;
 pub use api::*;
+pub use binance::*;
+pub use breaker::*;
 pub use coingecko::*;
+pub use fixed::*;
+pub use history::*;
+pub use kraken::*;
+pub use oracle::*;
+pub use pyth::*;
+pub use service::*;
+pub use snapshot::*;
+pub use stream::*;
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use fuels::{
+    accounts::{provider::Provider, wallet::WalletUnlocked},
+    programs::calls::Execution,
+    types::{Bits256, ContractId},
+};
+use futures::future::join_all;
+use tokio::sync::OnceCell;
+
+use crate::error::Error;
+
+use super::PriceApi;
+
+mod abi {
+    fuels::macros::abigen!(Contract(
+        name = "PythOracle",
+        abi = "abi/pyth-oracle-abi.json"
+    ));
+}
+
+use abi::PythOracle;
+
+/// TAI64 label of the unix epoch, Pyth on Fuel publishes times in TAI64
+const TAI64_UNIX_EPOCH: u64 = (1 << 62) + 10;
+
+/// Pyth oracle contract on Fuel, read with read-only simulated calls
+pub struct PythOracleApi {
+    pub node_url: String,
+    pub contract_id: ContractId,
+    /// Price ids mapped to Pyth USD price feed ids
+    pub feeds: HashMap<String, String>,
+    /// Time in milliseconds after which an oracle price is rejected
+    pub max_age: u64,
+    /// Connected on the first request
+    contract: OnceCell<PythOracle<WalletUnlocked>>,
+}
+
+#[async_trait]
+impl PriceApi for PythOracleApi {
+    fn name(&self) -> &'static str {
+        "pyth_oracle"
+    }
+
+    /// Get the prices of the given coin ids in USD
+    async fn prices(&self, ids: &[&str]) -> Result<Vec<Option<f64>>, Error> {
+        // A stale or missing feed leaves only its id unpriced
+        Ok(join_all(ids.iter().map(|&id| async move {
+            let feed = self.feeds.get(id)?;
+
+            match self.price(feed).await {
+                Ok(price) => Some(price),
+                Err(e) => {
+                    log::warn!("Error while getting {} {}: {}", self.name(), id, e);
+                    None
+                }
+            }
+        }))
+        .await)
+    }
+}
+
+impl PythOracleApi {
+    pub fn new(
+        node_url: String,
+        contract_id: ContractId,
+        feeds: HashMap<String, String>,
+        max_age: u64,
+    ) -> Self {
+        Self {
+            node_url,
+            contract_id,
+            feeds,
+            max_age,
+            contract: OnceCell::new(),
+        }
+    }
+
+    /// Contract instance with a throwaway wallet, read-only calls need no funds
+    async fn contract(&self) -> Result<&PythOracle<WalletUnlocked>, Error> {
+        self.contract
+            .get_or_try_init(|| async {
+                let provider = Provider::connect(&self.node_url).await?;
+                let wallet = WalletUnlocked::new_random(Some(provider));
+                Ok::<_, Error>(PythOracle::new(self.contract_id, wallet))
+            })
+            .await
+    }
+
+    /// Get the latest price of the feed with the exponent applied
+    pub async fn price(&self, feed: &str) -> Result<f64, Error> {
+        let feed_id = Bits256::from_hex_str(feed)
+            .map_err(|e| Error::PriceApi(format!("Pyth oracle: invalid feed {}: {}", feed, e)))?;
+
+        let price = self
+            .contract()
+            .await?
+            .methods()
+            .price_unsafe(feed_id)
+            .simulate(Execution::StateReadOnly)
+            .await?
+            .value;
+
+        let publish_time = price.publish_time.saturating_sub(TAI64_UNIX_EPOCH);
+        let age = (chrono::Utc::now().timestamp() as u64).saturating_sub(publish_time);
+        if age * 1000 > self.max_age {
+            return Err(Error::PriceApi(format!(
+                "Pyth oracle: price of {} is {}s old",
+                feed, age
+            )));
+        }
+
+        // The exponent is stored as the magnitude of a negative exponent
+        Ok(price.price as f64 / 10f64.powi(price.exponent as i32))
+    }
+}
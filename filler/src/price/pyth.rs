@@ -0,0 +1,114 @@
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::error::Error;
+
+use super::{get_json, PriceApi};
+
+/// Pyth Hermes price service
+pub struct PythApi {
+    pub host: String,
+    /// Price ids mapped to Pyth USD price feed ids
+    pub feeds: HashMap<String, String>,
+    /// Request timeout
+    pub timeout: Duration,
+    pub client: Client,
+}
+
+#[async_trait]
+impl PriceApi for PythApi {
+    fn name(&self) -> &'static str {
+        "pyth"
+    }
+
+    /// Get the prices of the given coin ids in USD
+    async fn prices(&self, ids: &[&str]) -> Result<Vec<Option<f64>>, Error> {
+        // Ids without a feed are left unpriced
+        let feeds = ids
+            .iter()
+            .map(|&id| {
+                self.feeds
+                    .get(id)
+                    .map(|feed| feed.trim_start_matches("0x").to_lowercase())
+            })
+            .collect::<Vec<_>>();
+        let requested = feeds.iter().flatten().collect::<Vec<_>>();
+        if requested.is_empty() {
+            return Ok(vec![None; ids.len()]);
+        }
+
+        let updates = self.latest_price(&requested).await?;
+        Ok(feeds
+            .iter()
+            .map(|feed| {
+                let feed = feed.as_ref()?;
+                updates
+                    .parsed
+                    .iter()
+                    .find(|update| update.id == *feed)
+                    .and_then(|update| update.price.value())
+            })
+            .collect())
+    }
+}
+
+impl PythApi {
+    pub fn new(host: String, feeds: HashMap<String, String>, timeout: Duration) -> Self {
+        Self {
+            host,
+            feeds,
+            timeout,
+            client: Client::new(),
+        }
+    }
+
+    async fn get<R: DeserializeOwned>(&self, endpoint: &str) -> Result<R, Error> {
+        let request = self.client.get(&format!("{}{}", self.host, endpoint));
+        get_json("Pyth", request, self.timeout).await
+    }
+
+    /// Get the latest updates of the given price feeds
+    pub async fn latest_price<Feed: AsRef<str>>(
+        &self,
+        feeds: &[Feed],
+    ) -> Result<PriceUpdates, Error> {
+        let feeds = feeds
+            .iter()
+            .map(|feed| format!("ids%5B%5D={}", feed.as_ref()))
+            .collect::<Vec<_>>();
+
+        let req = format!("/v2/updates/price/latest?{}&parsed=true", feeds.join("&"));
+
+        self.get(&req).await
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PriceUpdates {
+    pub parsed: Vec<PriceFeed>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PriceFeed {
+    pub id: String,
+    pub price: FeedPrice,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FeedPrice {
+    pub price: String,
+    pub conf: String,
+    pub expo: i32,
+    pub publish_time: u64,
+}
+
+impl FeedPrice {
+    /// Price with the exponent applied
+    pub fn value(&self) -> Option<f64> {
+        let price = self.price.parse::<f64>().ok()?;
+        Some(price * 10f64.powi(self.expo))
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    cmp,
+    collections::{HashMap, HashSet},
+    sync::{self, Arc},
+    time::Duration,
+};
+use tokio::{sync::watch, task::JoinHandle, time};
+use tokio_util::sync::CancellationToken;
+
+use crate::{config::Config, error::Error, operation::RetryPolicy};
+
+use super::{pair_key, PriceApi, PriceSnapshot};
+
+/// Latest prices fetched by the price service
+#[derive(Debug, Clone, Default)]
+pub struct PriceCache {
+    /// USD prices by price ids
+    pub usd: HashMap<String, PriceSnapshot<f64>>,
+    /// Directly priced pairs by pair keys
+    pub pairs: HashMap<String, PriceSnapshot<f64>>,
+}
+
+impl PriceCache {
+    /// Price of the base coin id in terms of the quote coin id, directly or via USD
+    pub fn price(&self, base: &str, quote: &str) -> Option<PriceSnapshot<f64>> {
+        if let Some(price) = self.pairs.get(&pair_key(base, quote)) {
+            return Some(*price);
+        }
+
+        Some(self.usd.get(base)?.cross(self.usd.get(quote)?))
+    }
+}
+
+/// Polls the price API for all assets at once within the request budget
+/// and shares the prices with every market bot
+pub struct PriceService {
+    pub price_api: Arc<dyn PriceApi>,
+    /// Price ids of all configured assets
+    pub ids: Vec<String>,
+    /// Pairs requested by the bots
+    pub pairs: sync::Mutex<HashSet<(String, String)>>,
+
+    /// Maximum number of price API requests per minute
+    pub requests_per_minute: u32,
+    pub retry_policy: RetryPolicy,
+
+    cache_tx: watch::Sender<Arc<PriceCache>>,
+}
+
+impl PriceService {
+    pub fn new(price_api: Arc<dyn PriceApi>, config: &Config) -> Self {
+        let mut ids = config
+            .assets
+            .values()
+            .map(|asset| asset.price_id.clone())
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+
+        let (cache_tx, _) = watch::channel(Arc::new(PriceCache::default()));
+
+        Self {
+            price_api,
+            ids,
+            pairs: sync::Mutex::new(HashSet::new()),
+            requests_per_minute: config.price.requests_per_minute,
+            retry_policy: RetryPolicy {
+                max_attempts: u32::MAX,
+                base_delay: Duration::from_millis(config.price.retry_base_delay),
+                max_delay: Duration::from_millis(config.price.retry_max_delay),
+            },
+            cache_tx,
+        }
+    }
+
+    /// Subscribe to the prices, the pair is priced directly when the API supports it
+    pub fn subscribe(&self, base: &str, quote: &str) -> watch::Receiver<Arc<PriceCache>> {
+        self.pairs
+            .lock()
+            .unwrap()
+            .insert((base.to_string(), quote.to_string()));
+
+        self.cache_tx.subscribe()
+    }
+
+    /// Delay between fetches which spreads the request budget evenly
+    pub fn interval(&self) -> Duration {
+        let requests = 1 + self.pairs.lock().unwrap().len();
+        Duration::from_secs_f64(60.0 * requests as f64 / self.requests_per_minute as f64)
+    }
+
+    pub fn start(self: Arc<Self>, shutdown: CancellationToken) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut failures = 0;
+
+            loop {
+                let delay = match self.fetch().await {
+                    Ok(cache) => {
+                        failures = 0;
+                        self.cache_tx.send_replace(Arc::new(cache));
+                        self.interval()
+                    }
+                    Err(e) => {
+                        failures += 1;
+                        log::error!(
+                            "Error while getting {} prices: {}",
+                            self.price_api.name(),
+                            e
+                        );
+
+                        // Bots stop quoting until the prices recover
+                        self.cache_tx.send_replace(Arc::new(PriceCache::default()));
+                        cmp::max(self.interval(), self.retry_policy.backoff(failures))
+                    }
+                };
+
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = time::sleep(delay) => {}
+                }
+            }
+        })
+    }
+
+    /// Get USD prices of all assets in one request and the requested pairs directly
+    async fn fetch(&self) -> Result<PriceCache, Error> {
+        let ids = self.ids.iter().map(String::as_str).collect::<Vec<_>>();
+        let snapshots = self.price_api.snapshots(&ids).await?;
+
+        let mut cache = PriceCache {
+            // Markets of the unpriced assets stop quoting, the others go on
+            usd: self
+                .ids
+                .iter()
+                .cloned()
+                .zip(snapshots)
+                .filter_map(|(id, snapshot)| Some((id, snapshot?)))
+                .collect(),
+            pairs: HashMap::new(),
+        };
+
+        let pairs = self
+            .pairs
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        for (base, quote) in pairs {
+            match self.price_api.pair_snapshot(&base, &quote).await {
+                Ok(Some(price)) => {
+                    cache.pairs.insert(pair_key(&base, &quote), price);
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("Error while getting {}/{}: {}", base, quote, e),
+            }
+        }
+
+        Ok(cache)
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::{Duration, Instant};
+
+/// Price with its origin and freshness
+#[derive(Debug, Clone, Copy)]
+pub struct PriceSnapshot<T = u64> {
+    pub value: T,
+    /// Provider name
+    pub source: &'static str,
+    pub fetched_at: Instant,
+    /// Share of the sources agreeing on the price, from 0 to 1
+    pub confidence: f64,
+    /// Number of sources agreeing on the price
+    pub sources: usize,
+}
+
+impl<T> PriceSnapshot<T> {
+    pub fn new(value: T, source: &'static str, confidence: f64) -> Self {
+        Self {
+            value,
+            source,
+            fetched_at: Instant::now(),
+            confidence,
+            sources: 1,
+        }
+    }
+
+    pub fn age(&self) -> Duration {
+        self.fetched_at.elapsed()
+    }
+
+    /// Check if the price is not older than the max age
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        self.age() <= max_age
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> PriceSnapshot<U> {
+        PriceSnapshot {
+            value: f(self.value),
+            source: self.source,
+            fetched_at: self.fetched_at,
+            confidence: self.confidence,
+            sources: self.sources,
+        }
+    }
+}
+
+impl PriceSnapshot<f64> {
+    /// Price of this asset in terms of the other one, as old and as uncertain as the worst of both
+    pub fn cross(&self, other: &PriceSnapshot<f64>) -> PriceSnapshot<f64> {
+        PriceSnapshot {
+            value: self.value / other.value,
+            source: if self.source == other.source {
+                self.source
+            } else {
+                "mixed"
+            },
+            fetched_at: self.fetched_at.min(other.fetched_at),
+            confidence: self.confidence.min(other.confidence),
+            sources: self.sources.min(other.sources),
+        }
+    }
+}
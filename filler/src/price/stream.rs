@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::{config::PriceStreamConfig, error::Error, types::Sender};
+
+use super::{BinanceStream, PriceSnapshot};
+
+/// Price of one of the streamed coin ids
+#[derive(Debug, Clone, Copy)]
+pub struct PriceUpdate {
+    /// Index of the id in the stream request
+    pub index: usize,
+    /// Price in USD, or in the quote coin for a pair id
+    pub price: PriceSnapshot<f64>,
+}
+
+#[async_trait]
+pub trait PriceStream: Send + Sync {
+    /// Provider name for logs
+    fn name(&self) -> &'static str;
+
+    /// Stream the prices of the given coin ids in USD and of the pair ids (ethereum/bitcoin)
+    /// in the quote coin until the connection is closed, unsupported ids are skipped
+    async fn stream(&self, ids: &[&str], updates: Sender<PriceUpdate>) -> Result<(), Error>;
+}
+
+impl From<&PriceStreamConfig> for Arc<dyn PriceStream> {
+    fn from(config: &PriceStreamConfig) -> Self {
+        match config.clone() {
+            PriceStreamConfig::Binance { host, symbols } => {
+                Arc::new(BinanceStream::new(host, symbols))
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
 mod strategy;
+mod sweeper;
 
 pub use strategy::*;
+pub use sweeper::*;
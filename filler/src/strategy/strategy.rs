@@ -1,10 +1,19 @@
 use rand::Rng;
 use std::{cmp, sync::Arc, time::Duration};
-use tokio::{sync::RwLock, task::JoinHandle, time};
+use tokio::{
+    sync::{broadcast, watch, RwLock},
+    task::JoinHandle,
+    time,
+};
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    operation::{OpenOrderOperation, Operation, OperationMessage},
+    config::Config,
+    operation::{
+        OpenOrderOperation, Operation, OperationMessage, OperationResult, OperationStatus,
+    },
     orderbook::{OrderType, Orderbook},
+    price::{PriceHistory, PriceSnapshot},
     types::{Amount, Asset, Sender},
 };
 
@@ -12,17 +21,29 @@ use crate::{
 pub struct Strategy {
     /// Interval in milliseconds
     pub interval: u64,
+    /// Maximum amount to trade
+    pub max_amount: f64,
+    /// Time in milliseconds after which the external price is treated as missing
+    pub max_price_age: u64,
     pub base: Asset,
     pub quote: Asset,
 }
 
 impl Strategy {
     /// Create a new strategy
-    pub fn new(base: Asset, quote: Asset, interval: u64) -> Self {
+    pub fn new(
+        base: Asset,
+        quote: Asset,
+        interval: u64,
+        max_amount: f64,
+        max_price_age: u64,
+    ) -> Self {
         Self {
             base,
             quote,
             interval,
+            max_amount,
+            max_price_age,
         }
     }
 
@@ -30,31 +51,84 @@ impl Strategy {
     pub async fn start(
         &self,
         orderbook: Arc<RwLock<Orderbook>>,
-        last_external_price: Arc<RwLock<Option<u64>>>,
+        last_external_price: Arc<RwLock<Option<PriceSnapshot>>>,
+        price_history: Arc<RwLock<PriceHistory>>,
         operation_tx: Sender<OperationMessage>,
-        max_amount: f64,
+        mut results_rx: broadcast::Receiver<OperationResult>,
+        mut config_rx: watch::Receiver<Arc<Config>>,
+        shutdown: CancellationToken,
     ) -> JoinHandle<()> {
-        let interval = self.interval;
+        let mut interval = self.interval;
+        let mut max_amount = self.max_amount;
+        let max_price_age = Duration::from_millis(self.max_price_age);
         let base = self.base.clone();
         let quote = self.quote.clone();
 
         tokio::spawn(async move {
             // ...
             loop {
-                time::sleep(Duration::from_millis(interval)).await;
+                // Swap parameters of the reloaded config
+                if config_rx.has_changed().unwrap_or(false) {
+                    let config = config_rx.borrow_and_update();
+                    interval = config.interval;
+                    max_amount = config.max_amount;
+                    log::info!(
+                        "Strategy reloaded: interval {}, max amount {}",
+                        interval,
+                        max_amount
+                    );
+                }
+
+                tokio::select! {
+                    _ = shutdown.cancelled() => {
+                        log::info!("Stopping strategy...");
+                        break;
+                    }
+                    _ = time::sleep(Duration::from_millis(interval)) => {}
+                }
+
+                // Check outcomes of the submitted operations
+                loop {
+                    match results_rx.try_recv() {
+                        Ok(OperationResult {
+                            operations,
+                            status: OperationStatus::Reverted(reason),
+                            ..
+                        }) => {
+                            log::warn!("{} operations reverted: {}", operations.len(), reason);
+                        }
+                        Ok(_) | Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                        Err(_) => break,
+                    }
+                }
 
-                let last_external_price = last_external_price.read().await;
+                // Stale price is as good as no price
+                let last_external_price = *last_external_price.read().await;
+                let last_external_price =
+                    last_external_price.filter(|price| price.is_fresh(max_price_age));
                 if last_external_price.is_none() {
                     // log::info!("No external price, skipping...");
                     continue;
                 }
 
+                // Market conditions over the price age window, for spread setting
+                {
+                    let history = price_history.read().await;
+                    log::debug!(
+                        "VOLATILITY: ewma {:?}, realized {:?}, twap {:?}, momentum {:?}",
+                        history.ewma_volatility(0.94),
+                        history.realized_volatility(max_price_age),
+                        history.twap(max_price_age),
+                        history.momentum(max_price_age),
+                    );
+                }
+
                 let orderbook = orderbook.read().await;
                 // if orderbook.is
                 let mut rng = rand::thread_rng();
 
                 // Random strategy for now
-                let price = last_external_price.unwrap();
+                let price = last_external_price.unwrap().value;
                 let (order_type, price) = if rng.gen_bool(0.5) {
                     (
                         OrderType::Buy,
@@ -83,6 +157,7 @@ impl Strategy {
                         amount,
                         price,
                     }),
+                    trader: None,
                 };
 
                 if operation_tx.is_closed() {
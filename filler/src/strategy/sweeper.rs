@@ -0,0 +1,240 @@
+use fuels::types::Address;
+use spark_market_sdk::SparkMarketContract;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    sync::{watch, RwLock},
+    task::JoinHandle,
+    time,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::Config,
+    operation::{CancelOrderOperation, Operation, OperationMessage},
+    orderbook::{open_order, user_orders, OpenOrder, OrderId, Orderbook},
+    price::PriceSnapshot,
+    types::Sender,
+};
+
+/// Cancels own orders which rest too long or drifted away from the external price
+#[derive(Debug, Clone)]
+pub struct Sweeper {
+    /// Interval in milliseconds
+    pub interval: u64,
+    /// Order time to live in seconds
+    pub order_ttl: u64,
+    /// Maximum relative distance between order price and external price
+    pub max_price_deviation: f64,
+    /// Time in milliseconds after which the external price is ignored
+    pub max_price_age: u64,
+}
+
+impl From<&Config> for Sweeper {
+    fn from(config: &Config) -> Self {
+        Self::new(
+            config.sweep_interval,
+            config.order_ttl,
+            config.max_price_deviation,
+            config.price.max_age,
+        )
+    }
+}
+
+impl Sweeper {
+    pub fn new(
+        interval: u64,
+        order_ttl: u64,
+        max_price_deviation: f64,
+        max_price_age: u64,
+    ) -> Self {
+        Self {
+            interval,
+            order_ttl,
+            max_price_deviation,
+            max_price_age,
+        }
+    }
+
+    /// Check if the order created at the timestamp should be cancelled
+    pub fn is_stale(
+        &self,
+        order: &OpenOrder,
+        timestamp: u64,
+        now: u64,
+        price: Option<u64>,
+    ) -> bool {
+        let is_expired = now.saturating_sub(timestamp) > self.order_ttl;
+        // A zero price can't be compared with
+        let is_off_price = price.filter(|&price| price > 0).is_some_and(|price| {
+            let deviation = (order.price as f64 - price as f64).abs() / price as f64;
+            deviation > self.max_price_deviation
+        });
+
+        is_expired || is_off_price
+    }
+
+    /// Run the sweeper over orders of the given traders
+    pub async fn start(
+        &self,
+        traders: HashSet<Address>,
+        market_contract: Arc<RwLock<SparkMarketContract>>,
+        orderbook: Arc<RwLock<Orderbook>>,
+        last_external_price: Arc<RwLock<Option<PriceSnapshot>>>,
+        operation_tx: Sender<OperationMessage>,
+        mut config_rx: watch::Receiver<Arc<Config>>,
+        shutdown: CancellationToken,
+    ) -> JoinHandle<()> {
+        let mut sweeper = self.clone();
+
+        tokio::spawn(async move {
+            // Own orders with the time they were first seen, orders don't change but the amount
+            let mut known = HashMap::<OrderId, (u64, OpenOrder)>::new();
+
+            loop {
+                // Swap parameters of the reloaded config
+                if config_rx.has_changed().unwrap_or(false) {
+                    sweeper = Sweeper::from(config_rx.borrow_and_update().as_ref());
+                    log::info!("Sweeper reloaded: {:?}", sweeper);
+                }
+
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = time::sleep(Duration::from_millis(sweeper.interval)) => {}
+                }
+
+                let max_price_age = Duration::from_millis(sweeper.max_price_age);
+                let price = *last_external_price.read().await;
+                let price = price
+                    .filter(|price| price.is_fresh(max_price_age))
+                    .map(|price| price.value);
+                let now = chrono::Utc::now().timestamp() as u64;
+
+                // The subscribed book has only the best orders,
+                // so own orders are taken from the market contract
+                let own_orders = {
+                    let market_contract = market_contract.read().await;
+                    let own_orders =
+                        match user_orders(&market_contract, traders.iter().cloned()).await {
+                            Ok(own_orders) => own_orders,
+                            Err(e) => {
+                                log::error!("Error while getting own orders: {}", e);
+                                continue;
+                            }
+                        };
+
+                    let ids = own_orders.iter().map(|(_, id)| id).collect::<HashSet<_>>();
+                    known.retain(|id, _| ids.contains(id));
+
+                    for (_, id) in &own_orders {
+                        if known.contains_key(id) {
+                            continue;
+                        }
+                        match open_order(&market_contract, id).await {
+                            Ok(Some(order)) => {
+                                known.insert(id.clone(), (now, order));
+                            }
+                            Ok(None) => {}
+                            Err(e) => log::error!("Error while getting order {}: {}", id, e),
+                        }
+                    }
+
+                    own_orders
+                };
+
+                // Creation time is known from the indexer for orders in the book
+                let timestamps = orderbook
+                    .read()
+                    .await
+                    .orders_by_users(&traders)
+                    .into_iter()
+                    .map(|(_, order)| (order.id.clone(), order.timestamp))
+                    .collect::<HashMap<_, _>>();
+
+                let stale_orders = own_orders
+                    .into_iter()
+                    .filter(|(_, id)| {
+                        known.get(id).is_some_and(|(seen_at, order)| {
+                            let timestamp = timestamps.get(id).copied().unwrap_or(*seen_at);
+                            sweeper.is_stale(order, timestamp, now, price)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                if operation_tx.is_closed() {
+                    log::info!("Operation channel closed, stopping sweeper...");
+                    break;
+                }
+
+                for (trader, order_id) in stale_orders {
+                    log::info!("STALE ORDER: {}", order_id);
+
+                    // Duplicates are dropped by the operation manager
+                    let message = OperationMessage {
+                        operation: Operation::CancelOrder(CancelOrderOperation { order_id }),
+                        trader: Some(trader),
+                    };
+
+                    if let Err(e) = operation_tx.send(message) {
+                        log::error!("Error sending operation: {:?}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::OrderType;
+
+    fn sweeper() -> Sweeper {
+        Sweeper::new(1000, 60, 0.05, 15000)
+    }
+
+    fn order(price: u64) -> OpenOrder {
+        OpenOrder {
+            id: "0x01".into(),
+            order_type: OrderType::Buy,
+            amount: 10,
+            price,
+        }
+    }
+
+    #[test]
+    fn is_stale_when_expired() {
+        let sweeper = sweeper();
+
+        assert!(!sweeper.is_stale(&order(1000), 100, 160, Some(1000)));
+        assert!(sweeper.is_stale(&order(1000), 100, 161, Some(1000)));
+        assert!(sweeper.is_stale(&order(1000), 100, 161, None));
+    }
+
+    #[test]
+    fn is_stale_when_off_price() {
+        let sweeper = sweeper();
+
+        assert!(!sweeper.is_stale(&order(1050), 100, 100, Some(1000)));
+        assert!(sweeper.is_stale(&order(1051), 100, 100, Some(1000)));
+        assert!(sweeper.is_stale(&order(949), 100, 100, Some(1000)));
+    }
+
+    #[test]
+    fn is_stale_ignores_missing_and_zero_price() {
+        let sweeper = sweeper();
+
+        assert!(!sweeper.is_stale(&order(1000), 100, 100, None));
+        assert!(!sweeper.is_stale(&order(1000), 100, 100, Some(0)));
+    }
+
+    #[test]
+    fn is_stale_tolerates_clock_behind_order() {
+        let sweeper = sweeper();
+
+        assert!(!sweeper.is_stale(&order(1000), 200, 100, Some(1000)));
+    }
+}
@@ -1,11 +1,12 @@
 use fuels::types::AssetId;
+use serde::{Deserialize, Serialize};
 use std::{ops::Deref, sync::Arc};
 use tokio::sync::{
     mpsc::{UnboundedReceiver, UnboundedSender},
     Mutex,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Asset {
     pub id: AssetId,
     pub decimals: u8,
@@ -17,7 +18,7 @@ impl Asset {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Amount(pub u64);
 
 impl From<u64> for Amount {